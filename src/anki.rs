@@ -1,3 +1,4 @@
+use crate::error::{self, Context};
 use crate::Config;
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -7,23 +8,44 @@ fn request(action: String, params: Value) -> Value {
     json!({"action": action, "params": params, "version": 6})
 }
 
+const URL: &str = "http://localhost:8765";
+
 fn invoke(action: String, params: Value) -> Result<Value, Box<dyn Error>> {
+    let message = format!(
+        "Failed to send {} to {}, check that anki is running with AnkiConnect",
+        action, URL
+    );
     let request_json = request(action, params);
 
     let client = reqwest::blocking::Client::new();
     let response = client
-        .post("http://localhost:8765")
+        .post(URL)
         .body(request_json.to_string())
-        .send()?
-        .text()?;
-
-    let response = serde_json::from_str(&response)?;
+        .send()
+        .and_then(|x| x.text())
+        .context(error::Kind::AnkiConnect, &message)?;
 
-    // @TODO error handling
+    let response = serde_json::from_str(&response).context(error::Kind::AnkiConnect, &message)?;
 
     Ok(response)
 }
 
+/// Get the array in the result of a response, the error of the response says why it is missing.
+fn result_array(response: &Value, name: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+    match response["result"].as_array() {
+        Some(array) => Ok(array.clone()),
+        None => Err(error::Error::new(
+            error::Kind::AnkiConnect,
+            format!(
+                "The response does not contain {}: {}",
+                name,
+                response["error"].as_str().unwrap_or("no error message")
+            ),
+        )
+        .into()),
+    }
+}
+
 fn url_for_expression(expression: &str, reading: &str) -> (String, String) {
     let url_string = format!(
         "https://assets.languagepod101.com/dictionary/japanese/audiomp3.php?kanji={}&kana={}",
@@ -34,27 +56,39 @@ fn url_for_expression(expression: &str, reading: &str) -> (String, String) {
     (url_string, file_string)
 }
 
-fn verify_fields(field_list: &Vec<Vec<String>>) {
+fn verify_fields(field_list: &[Vec<String>]) -> Result<(), error::Error> {
     if field_list.len() != 2 {
-        panic!("The configuration data either the fields array or values array is missing.");
+        return Err(error::Error::new(
+            error::Kind::Config,
+            "fields in [anki] needs an array of field names and an array of values".to_string(),
+        ));
     }
 
     let fields = &field_list[0];
     let values = &field_list[1];
 
     if fields.len() != values.len() {
-        panic!("The configuration data is invalid the fields and values array are not the same length.");
+        return Err(error::Error::new(
+            error::Kind::Config,
+            format!(
+                "fields in [anki] has {} field names but {} values",
+                fields.len(),
+                values.len()
+            ),
+        ));
     }
+
+    Ok(())
 }
 
 fn create_fields(
-    field_list: &Vec<Vec<String>>,
+    field_list: &[Vec<String>],
     definition: &str,
     expression: &str,
     reading: &str,
     sentence: &str,
-) -> Value {
-    verify_fields(field_list);
+) -> Result<Value, Box<dyn Error>> {
+    verify_fields(field_list)?;
     let field_value_iter = field_list[0].iter().zip(field_list[1].iter());
 
     // if the reading is blank, set reading to equal expression
@@ -81,7 +115,7 @@ fn create_fields(
         field_map.insert(f.to_string(), value.to_string());
     }
 
-    json!(field_map)
+    Ok(json!(field_map))
 }
 
 fn create_options(allow_duplicates: bool, duplicate_scope: String) -> Value {
@@ -91,8 +125,8 @@ fn create_options(allow_duplicates: bool, duplicate_scope: String) -> Value {
     })
 }
 
-fn create_audio_fields(field_list: &Vec<Vec<String>>) -> Value {
-    verify_fields(field_list);
+fn create_audio_fields(field_list: &[Vec<String>]) -> Result<Value, Box<dyn Error>> {
+    verify_fields(field_list)?;
     let field_value_iter = field_list[0].iter().zip(field_list[1].iter());
 
     let mut audio_field_list: Vec<String> = Vec::new();
@@ -102,7 +136,7 @@ fn create_audio_fields(field_list: &Vec<Vec<String>>) -> Value {
         }
     }
 
-    json!(audio_field_list)
+    Ok(json!(audio_field_list))
 }
 
 fn create_audio_list(audio_fields: &Value, url_list: &Vec<(String, String)>) -> Vec<Value> {
@@ -121,6 +155,23 @@ fn create_audio_list(audio_fields: &Value, url_list: &Vec<(String, String)>) ->
     audio_list
 }
 
+/// Combine the tags from the config with the tags of the expression.
+///
+/// Anki tags cannot contain spaces, so spaces are replaced with underscores.
+fn create_tag_list(config_tag_list: &[String], tag_list: &[String]) -> Vec<String> {
+    let mut combined_list: Vec<String> = config_tag_list.to_vec();
+
+    for tag in tag_list.iter() {
+        let tag = tag.split_whitespace().collect::<Vec<&str>>().join("_");
+
+        if !combined_list.contains(&tag) {
+            combined_list.push(tag);
+        }
+    }
+
+    combined_list
+}
+
 fn create_note(
     p: &Config,
     definition: &str,
@@ -128,23 +179,24 @@ fn create_note(
     reading: &str,
     sentence: &str,
     url_list: &Vec<(String, String)>,
-) -> Value {
+    tag_list: &[String],
+) -> Result<Value, Box<dyn Error>> {
     let anki = p.anki();
     let field_list = anki.fields();
 
     let deck_name = anki.deck_name();
     let model_name = anki.model_name();
-    let fields = create_fields(field_list, definition, expression, reading, sentence);
+    let fields = create_fields(field_list, definition, expression, reading, sentence)?;
     let options = create_options(anki.allow_duplicates(), anki.duplicate_scope().to_string());
-    let tags = anki.tags();
-    let audio_fields = create_audio_fields(field_list);
+    let tags = create_tag_list(anki.tags(), tag_list);
+    let audio_fields = create_audio_fields(field_list)?;
     let mut audio_list: Vec<Value> = Vec::new();
 
     if anki.audio() {
         audio_list = create_audio_list(&audio_fields, url_list);
     }
 
-    json!({
+    Ok(json!({
             "note": {
                 "deckName": deck_name,
                 "modelName": model_name,
@@ -153,7 +205,7 @@ fn create_note(
                 "tags": tags,
                 "audio": audio_list
             }
-    })
+    }))
 }
 
 fn note_id_list(p: &Config) -> Result<Vec<Value>, Box<dyn Error>> {
@@ -162,39 +214,19 @@ fn note_id_list(p: &Config) -> Result<Vec<Value>, Box<dyn Error>> {
         json!({ "query": format!("deck:\"{}\"", p.anki().deck_name()) }),
     )?;
 
-    if !result["result"].is_array() {
-        panic!("Response from Anki Connect does not contain an note id array");
-    }
-
-    let mut id_list: Vec<Value> = Vec::new();
-    for id in result["result"].as_array().unwrap().iter() {
-        id_list.push(id.clone());
-    }
-
-    Ok(id_list)
+    result_array(&result, "a note id array")
 }
 
 fn note_info_list_for_id_list(id_list: &Vec<Value>) -> Result<Vec<Value>, Box<dyn Error>> {
     let result = invoke("notesInfo".to_string(), json!({ "notes": id_list }))?;
 
-    if !result["result"].is_array() {
-        panic!("Response from Anki Connect does not contain a note info array");
-    }
-
-    let mut info_list: Vec<Value> = Vec::new();
-    for info in result["result"].as_array().unwrap().iter() {
-        info_list.push(info.clone());
-    }
-
-    Ok(info_list)
+    result_array(&result, "a note info array")
 }
 
-fn expression_list_for_info_list(
-    p: &Config,
-    info_list: &Vec<Value>,
-) -> Result<Vec<String>, Box<dyn Error>> {
+/// Get the name of the anki field that holds the expression.
+fn expression_field(p: &Config) -> Result<String, Box<dyn Error>> {
     let fields = p.anki().fields();
-    verify_fields(fields);
+    verify_fields(fields)?;
     let field_value_iter = fields[0].iter().zip(fields[1].iter());
 
     let mut expression_field = "".to_string();
@@ -205,26 +237,74 @@ fn expression_list_for_info_list(
         }
     }
 
+    Ok(expression_field)
+}
+
+/// Get the value of the expression field from a note or card info object.
+fn expression_for_info(expression_field: &str, info: &Value) -> Result<String, Box<dyn Error>> {
+    match info["fields"][expression_field]["value"].as_str() {
+        Some(expression) => Ok(expression.to_string()),
+        None => Err(error::Error::new(
+            error::Kind::AnkiConnect,
+            format!(
+                "A note of the deck has no {} field: {}",
+                expression_field, info
+            ),
+        )
+        .into()),
+    }
+}
+
+fn expression_list_for_info_list(
+    p: &Config,
+    info_list: &[Value],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let expression_field = expression_field(p)?;
+
     let mut expression_list: Vec<String> = Vec::new();
     for note in info_list.iter() {
-        match note.is_object() {
-            true => {
-                let note = note.as_object().unwrap();
-                let fields = note["fields"].as_object().unwrap();
-                let expression = fields[&expression_field].as_object().unwrap()["value"]
-                    .as_str()
-                    .unwrap()
-                    .to_string();
-
-                expression_list.push(expression);
-            }
-            false => panic!("Invalid note in info_list"),
-        }
+        expression_list.push(expression_for_info(&expression_field, note)?);
     }
 
     Ok(expression_list)
 }
 
+fn card_id_list(p: &Config) -> Result<Vec<Value>, Box<dyn Error>> {
+    let result = invoke(
+        "findCards".to_string(),
+        json!({ "query": format!("deck:\"{}\"", p.anki().deck_name()) }),
+    )?;
+
+    result_array(&result, "a card id array")
+}
+
+fn card_info_list_for_id_list(id_list: &[Value]) -> Result<Vec<Value>, Box<dyn Error>> {
+    let result = invoke("cardsInfo".to_string(), json!({ "cards": id_list }))?;
+
+    result_array(&result, "a card info array")
+}
+
+/// Get the longest review interval in days for each expression.
+///
+/// Cards that are still in learning report their interval as negative seconds, they count as 0 days.
+fn interval_map_for_info_list(
+    p: &Config,
+    info_list: &[Value],
+) -> Result<HashMap<String, i64>, Box<dyn Error>> {
+    let expression_field = expression_field(p)?;
+
+    let mut interval_map: HashMap<String, i64> = HashMap::new();
+    for card in info_list.iter() {
+        let expression = expression_for_info(&expression_field, card)?;
+        let interval = card["interval"].as_i64().unwrap_or(0).max(0);
+
+        let entry = interval_map.entry(expression).or_insert(0);
+        *entry = (*entry).max(interval);
+    }
+
+    Ok(interval_map)
+}
+
 pub fn create_url_list(expression: &str, reading_list: &Vec<String>) -> Vec<(String, String)> {
     let mut url_list: Vec<(String, String)> = Vec::new();
     match reading_list.len() {
@@ -246,8 +326,11 @@ pub fn insert_note(
     reading: &str,
     sentence: &str,
     url_list: &Vec<(String, String)>,
+    tag_list: &[String],
 ) -> Result<(), Box<dyn Error>> {
-    let params = create_note(p, definition, expression, reading, sentence, url_list);
+    let params = create_note(
+        p, definition, expression, reading, sentence, url_list, tag_list,
+    )?;
     invoke("addNote".to_string(), params)?;
 
     Ok(())
@@ -261,6 +344,15 @@ pub fn expression_list(p: &Config) -> Result<Vec<String>, Box<dyn Error>> {
     Ok(expression_list)
 }
 
+/// Get the longest review interval in days for each expression in the deck.
+pub fn interval_map(p: &Config) -> Result<HashMap<String, i64>, Box<dyn Error>> {
+    let id_list = card_id_list(p)?;
+    let info_list = card_info_list_for_id_list(&id_list)?;
+    let interval_map = interval_map_for_info_list(p, &info_list)?;
+
+    Ok(interval_map)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,7 +403,7 @@ mod tests {
         );
 
         assert_eq!(
-            create_fields(&field_list, &definition, &expression, &reading, &sentence),
+            create_fields(&field_list, &definition, &expression, &reading, &sentence).unwrap(),
             fields
         );
     }
@@ -346,7 +438,7 @@ mod tests {
         );
 
         assert_eq!(
-            create_fields(&field_list, &definition, &expression, &reading, &sentence),
+            create_fields(&field_list, &definition, &expression, &reading, &sentence).unwrap(),
             fields
         );
     }
@@ -381,6 +473,83 @@ mod tests {
             sentence.clone(),
         );
 
-        assert_eq!(create_fields(&field_list, "", "", "", ""), fields);
+        assert_eq!(create_fields(&field_list, "", "", "", "").unwrap(), fields);
+    }
+
+    #[test]
+    fn create_fields_mismatched_fields() {
+        let field_list = vec![
+            vec!["Expression".to_string(), "Reading".to_string()],
+            vec!["expression".to_string()],
+        ];
+
+        let error = create_fields(&field_list, "", "塩", "", "").unwrap_err();
+
+        let error = error.downcast_ref::<error::Error>().unwrap();
+        assert_eq!(error.kind(), error::Kind::Config);
+        assert_eq!(
+            error.to_string(),
+            "Config error: fields in [anki] has 2 field names but 1 values"
+        );
+    }
+
+    #[test]
+    fn create_tag_list_extra_tags() {
+        let config_tag_list = vec!["vocabulist".to_string()];
+        let tag_list = vec![
+            "work terms".to_string(),
+            "vocabulist".to_string(),
+            "Genshin".to_string(),
+        ];
+
+        assert_eq!(
+            create_tag_list(&config_tag_list, &tag_list),
+            vec![
+                "vocabulist".to_string(),
+                "work_terms".to_string(),
+                "Genshin".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn interval_map_longest_interval() {
+        let field_list = vec![
+            vec!["Expression".to_string(), "Definition".to_string()],
+            vec!["expression".to_string(), "definition".to_string()],
+        ];
+        let anki = crate::config::AnkiConnect::new(
+            "Default".to_string(),
+            "Basic".to_string(),
+            false,
+            "deck".to_string(),
+            false,
+            field_list,
+            vec![],
+        );
+        let p = Config::new(
+            std::path::PathBuf::from("database"),
+            None,
+            "mecab".to_string(),
+            anki,
+        );
+
+        let card = |expression: &str, interval: i64| {
+            json!({
+                "fields": {
+                    "Expression": { "value": expression, "order": 0 },
+                    "Definition": { "value": "", "order": 1 }
+                },
+                "interval": interval
+            })
+        };
+
+        let info_list = vec![card("塩", 3), card("塩", 30), card("砂糖", -600)];
+
+        let interval_map =
+            interval_map_for_info_list(&p, &info_list).expect("Failed to get interval map");
+
+        assert_eq!(interval_map["塩"], 30);
+        assert_eq!(interval_map["砂糖"], 0);
     }
 }
@@ -0,0 +1,996 @@
+//! The commands of the command line, which are a layer over the library.
+//!
+//! They take the parsed arguments and print their results.
+
+use crate::{concordance, progress_bar};
+use clap::ArgMatches;
+use itertools::Itertools;
+use regex::Regex;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use vocabulist_rs::config::{profile, Config};
+use vocabulist_rs::error::{self, Context};
+use vocabulist_rs::word_list::{self, Column};
+use vocabulist_rs::{
+    config_root, date, export, Card, Coverage, Filter, FrequencyListOptions, ImportOptions,
+    MergePolicy, Order, Score, Search, Split, Vocabulist,
+};
+
+/// Get the tags given with --tag.
+fn tag_list(m: &ArgMatches) -> Vec<String> {
+    match m.values_of("tag") {
+        Some(values) => values.map(|x| x.to_string()).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Create the filter for the arguments shared by the commands that select expressions.
+///
+/// The query is the name of a saved query in the config or a filter expression.
+fn expression_filter(p: &Config, m: &ArgMatches) -> Result<Filter, Box<dyn Error>> {
+    let since = match m.value_of("since") {
+        Some(since) => Some(date::parse(since, date::now()?)?),
+        None => None,
+    };
+
+    let filter = Filter::new(
+        m.is_present("anki"),
+        m.is_present("excluded"),
+        m.is_present("learned"),
+    )
+    .tag_list(tag_list(m))
+    .since(since);
+
+    match m.value_of("query") {
+        Some(query) => match p.query(query) {
+            // a saved query that does not parse is an error in the config
+            Some(saved_query) => Ok(filter.parse(saved_query).context(
+                error::Kind::Config,
+                &format!("Invalid saved query {}", query),
+            )?),
+            None => filter.parse(query),
+        },
+        None => Ok(filter),
+    }
+}
+
+/// Parse the value of a numeric argument.
+fn number_arg<T: FromStr>(m: &ArgMatches, name: &str) -> Result<T, Box<dyn Error>> {
+    let value = m.value_of(name).unwrap_or_default();
+
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {}, expected a number", name, value).into())
+}
+
+/// The command line as it is shown in the history.
+fn command_line() -> String {
+    std::env::args().skip(1).collect::<Vec<String>>().join(" ")
+}
+
+/// Open the database of the profile, recording the command line in the history.
+fn open(p: Config) -> Result<Vocabulist, Box<dyn Error>> {
+    Ok(Vocabulist::open(p)?.command_line(&command_line()))
+}
+
+/// Format the terms of a score as name=value pairs.
+fn format_score(score: &Score) -> String {
+    score
+        .term_list()
+        .iter()
+        .map(|(name, value)| format!("{}={:.3}", name, value))
+        .join(" ")
+}
+
+pub fn import(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let seen_at = match m.value_of("date") {
+        Some(date) => Some(date::parse(date, date::now()?)?),
+        None => None,
+    };
+    let options = ImportOptions::new()
+        .cache(!m.is_present("no-cache"))
+        .seen_at(seen_at);
+
+    let mut vocabulist = open(p)?;
+    let summary = vocabulist.import_path(
+        Path::new(m.value_of("path").unwrap()),
+        &options,
+        &progress_bar::Reporter::default(),
+    )?;
+
+    if !vocabulist.config().exclusion_rules().is_empty() {
+        println!(
+            "Excluded {} new expressions using exclusion rules",
+            summary.excluded_count
+        );
+    }
+
+    Ok(())
+}
+
+pub fn list(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let filter = expression_filter(&p, m)?;
+    let vocabulist = open(p)?;
+
+    match m.is_present("pos") {
+        true => {
+            let is_excluded = m.is_present("excluded");
+            let is_asc = m.is_present("asc");
+            let limit = number_arg::<i32>(m, "number")?;
+
+            for pos in vocabulist.pos_list(is_excluded, is_asc, limit)? {
+                println!("{}", pos);
+            }
+        }
+        false => {
+            let is_explain = m.is_present("explain");
+            let order = match m.value_of("order") {
+                Some(order) => Order::parse(order)?,
+                None if is_explain => Order::Score,
+                None => Order::Frequency,
+            };
+            let is_asc = m.is_present("asc");
+            let limit = number_arg::<i32>(m, "number")?;
+
+            for (record, score) in vocabulist.expressions(&filter, order, is_asc, limit)? {
+                let mut column_list = vec![record.expression.to_string()];
+
+                if m.is_present("rank") {
+                    column_list.push(record.frequency.to_string());
+                    column_list.push(record.rank.map_or("-".to_string(), |x| x.to_string()));
+                }
+
+                if m.is_present("dispersion") {
+                    column_list.push(record.document_count.to_string());
+                    column_list.push(format!("{:.1}", record.per_million));
+                    column_list.push(
+                        record
+                            .dispersion
+                            .map_or("-".to_string(), |x| format!("{:.3}", x)),
+                    );
+                }
+
+                if is_explain {
+                    column_list.push(format!("{:.3}", score.total()));
+                    column_list.push(format_score(&score));
+                }
+
+                println!("{}", column_list.join("\t"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn export(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let format = export::Format::parse(m.value_of("format").unwrap())?;
+    let sentence_limit = number_arg::<i32>(m, "sentences")?;
+
+    let filter = expression_filter(&p, m)?;
+    let order = Order::parse(m.value_of("order").unwrap_or("frequency"))?;
+    let is_asc = m.is_present("asc");
+    let limit = number_arg::<i32>(m, "number")?;
+
+    let vocabulist = open(p)?;
+    let record_list = vocabulist.export_records(&filter, order, is_asc, limit, sentence_limit)?;
+
+    match m.value_of("output") {
+        Some(path) => export::write(
+            fs::File::create(path)
+                .context(error::Kind::Io, &format!("Failed to create {}", path))?,
+            &format,
+            &record_list,
+        )?,
+        None => export::write(std::io::stdout(), &format, &record_list)?,
+    }
+
+    Ok(())
+}
+
+pub fn exclude(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    if m.is_present("rules") {
+        let is_dry_run = m.is_present("dry-run");
+        let match_list = vocabulist.exclude_by_rules(is_dry_run)?;
+
+        if is_dry_run {
+            for (expression, rule) in &match_list {
+                println!("{}\t{}", expression, rule);
+            }
+        }
+
+        println!("Matched {} expressions", match_list.len());
+
+        return Ok(());
+    }
+
+    update_is_excluded(&mut vocabulist, m, true)
+}
+
+pub fn include(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    update_is_excluded(&mut vocabulist, m, false)
+}
+
+/// Exclude or include the expressions, or the pos with --pos, in the file given with the path.
+fn update_is_excluded(
+    vocabulist: &mut Vocabulist,
+    m: &ArgMatches,
+    is_excluded: bool,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(path) = m.value_of("path") {
+        let file_content = fs::read_to_string(path)
+            .context(error::Kind::Io, &format!("Failed to read {}", path))?;
+        let line_list: Vec<String> = file_content
+            .split_whitespace()
+            .map(|x| x.to_string())
+            .collect();
+
+        let progress = progress_bar::Reporter::default();
+        match m.is_present("pos") {
+            true => vocabulist.exclude_pos(&line_list, is_excluded, &progress)?,
+            false => vocabulist.exclude(&line_list, is_excluded, &progress)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the words for learn and unlearn from the arguments, a file or stdin.
+fn read_word_list(m: &ArgMatches) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut word_list: Vec<String> = match m.values_of("word") {
+        Some(values) => values.map(|x| x.to_string()).collect(),
+        None => Vec::new(),
+    };
+
+    let path = match m.value_of("path") {
+        Some(path) => Some(path),
+        None if word_list.is_empty() => Some("-"), // read from stdin when no words are given
+        None => None,
+    };
+
+    if let Some(path) = path {
+        let reader: Box<dyn std::io::Read> = match path {
+            "-" => Box::new(std::io::stdin()),
+            _ => Box::new(fs::File::open(path)?),
+        };
+
+        let list = match m.value_of("column") {
+            Some(column) => {
+                let column = word_list::Column::parse(column)?;
+                let delimiter = word_list::parse_delimiter(m.value_of("delimiter").unwrap())?;
+
+                word_list::read_delimited(reader, &column, delimiter, m.is_present("header"))?
+            }
+            None => word_list::read_plain(reader)?,
+        };
+
+        word_list.extend(list);
+    }
+
+    Ok(word_list)
+}
+
+fn update_is_learned(p: Config, m: &ArgMatches, is_learned: bool) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let word_list = read_word_list(m)?;
+    let count = vocabulist.learn(&word_list, is_learned, &progress_bar::Reporter::default())?;
+
+    if count > 0 {
+        println!(
+            "Added {} expressions that have not been imported yet",
+            count
+        );
+    }
+
+    Ok(())
+}
+
+pub fn priority(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let priority = number_arg::<i64>(m, "priority")?;
+    let word_list = read_word_list(m)?;
+    let count = vocabulist.prioritize(&word_list, priority, &progress_bar::Reporter::default())?;
+
+    if count > 0 {
+        println!("Skipped {} expressions that have not been imported", count);
+    }
+
+    Ok(())
+}
+
+pub fn learn(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    update_is_learned(p, m, true)
+}
+
+pub fn unlearn(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    update_is_learned(p, m, false)
+}
+
+pub fn tag(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let (m, is_tagged) = match m.subcommand() {
+        ("add", Some(m)) => (m, true),
+        ("remove", Some(m)) => (m, false),
+        _ => {
+            for (tag, count) in vocabulist.tags()?.iter() {
+                println!("{} ({})", tag, count);
+            }
+
+            return Ok(());
+        }
+    };
+
+    let tag = m.value_of("tag").unwrap();
+    let word_list = read_word_list(m)?;
+    let count = vocabulist.tag(
+        tag,
+        &word_list,
+        is_tagged,
+        &progress_bar::Reporter::default(),
+    )?;
+
+    if count > 0 {
+        println!("Skipped {} expressions that have not been imported", count);
+    }
+
+    Ok(())
+}
+
+pub fn frequency(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    match m.subcommand() {
+        ("import", Some(m)) => {
+            let path = Path::new(m.value_of("path").unwrap());
+
+            let optional_column = |name: &str| -> Result<Option<Column>, Box<dyn Error>> {
+                match m.value_of(name) {
+                    Some(column) => Ok(Some(Column::parse(column)?)),
+                    None => Ok(None),
+                }
+            };
+            let delimiter = match m.value_of("delimiter") {
+                Some(delimiter) => Some(word_list::parse_delimiter(delimiter)?),
+                None => None,
+            };
+
+            let options = FrequencyListOptions::new()
+                .name(m.value_of("name").map(|x| x.to_string()))
+                .column(Column::parse(m.value_of("column").unwrap())?)
+                .reading_column(optional_column("reading-column")?)
+                .rank_column(optional_column("rank-column")?)
+                .delimiter(delimiter)
+                .header(m.is_present("header"));
+
+            let (name, count) = vocabulist.import_frequency_list(
+                path,
+                &options,
+                &progress_bar::Reporter::default(),
+            )?;
+
+            println!("Imported {} entries into {}", count, name);
+        }
+        ("remove", Some(m)) => {
+            let name = m.value_of("name").unwrap();
+            vocabulist.remove_frequency_list(name)?;
+
+            println!("Removed {}", name);
+        }
+        _ => {
+            for (name, count) in vocabulist.frequency_lists()?.iter() {
+                println!("{} ({})", name, count);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn generate(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    if m.is_present("number") {
+        let max = number_arg::<i32>(m, "number")?;
+        let filter = expression_filter(&p, m)?;
+        let order = Order::parse(m.value_of("order").unwrap_or("score"))?;
+
+        let mut vocabulist = open(p)?;
+        vocabulist.generate(max, &filter, order, &progress_bar::Reporter::default())?;
+    }
+
+    Ok(())
+}
+
+pub fn sync(p: Config, _: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let learned_interval = p.anki().learned_interval();
+
+    let mut vocabulist = open(p)?;
+    let summary = vocabulist.sync(&progress_bar::Reporter::default())?;
+
+    println!();
+    println!(
+        "Marked {} expressions as learned and {} as not learned (interval of {} days)",
+        summary.learned_count, summary.unlearned_count, learned_interval
+    );
+
+    Ok(())
+}
+
+fn format_change_value(value: Option<i64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "NULL".to_string(),
+    }
+}
+
+pub fn history(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let vocabulist = open(p)?;
+
+    let number = number_arg::<i32>(m, "number")?;
+
+    for entry in vocabulist.history(number)?.iter() {
+        println!(
+            "{:>4}  {}  {:>5} changes  {}{}",
+            entry.id,
+            entry.created_at,
+            entry.change_count,
+            entry.command,
+            match (entry.is_undone, entry.is_reversible) {
+                (true, _) => " (undone)",
+                (false, false) => " (cannot be undone)",
+                (false, true) => "",
+            }
+        );
+
+        if m.is_present("verbose") {
+            for (label, change) in vocabulist.changes(entry.id)?.iter() {
+                println!(
+                    "        {} {}: {} -> {}",
+                    label,
+                    change.column_name,
+                    format_change_value(change.old_value),
+                    format_change_value(change.new_value)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn undo(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let number = number_arg::<i32>(m, "number")?;
+
+    let summary = vocabulist.undo(number)?;
+
+    if summary.undone_list.is_empty() && summary.blocking_entry.is_none() {
+        println!("Nothing to undo");
+    }
+
+    for entry in summary.undone_list.iter() {
+        println!("Undid {} ({} changes)", entry.command, entry.change_count);
+    }
+
+    if let Some(entry) = summary.blocking_entry {
+        println!(
+            "Stopped at {}, which cannot be undone, undoing older commands would overwrite its changes",
+            entry.command
+        );
+    }
+
+    Ok(())
+}
+
+pub fn search(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let vocabulist = open(p)?;
+
+    let query = m.value_of("query").unwrap();
+    let limit = number_arg::<i32>(m, "number")?;
+    let width = number_arg::<usize>(m, "width")?;
+    let is_highlighted = !m.is_present("plain");
+
+    let search = match (m.is_present("lemma"), m.is_present("substring")) {
+        (true, _) => Search::Lemma,
+        (_, true) => Search::Substring,
+        _ => Search::Phrase,
+    };
+
+    // each sentence with the strings to highlight in it
+    let sentence_list = vocabulist.search(query, search, limit)?;
+
+    for (sentence, needle_list) in sentence_list.iter() {
+        for range in concordance::match_list(sentence, needle_list) {
+            println!(
+                "{}",
+                concordance::kwic_line(sentence, range, width, is_highlighted)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn recount(p: Config, _: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let count = vocabulist.recount()?;
+
+    println!("Repaired the frequency of {} expressions", count);
+
+    Ok(())
+}
+
+pub fn cache(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let cache_path = p.cache_path();
+    let vocabulist = open(p)?;
+
+    match m.subcommand() {
+        ("clear", Some(_)) => {
+            let count = vocabulist.clear_cache()?;
+
+            println!("Removed {} cached sentences", count);
+        }
+        _ => {
+            let stats = vocabulist.cache_stats()?;
+
+            println!("Cache: {}", cache_path.display());
+            println!("Size: {} KiB", stats.size / 1024);
+            println!("Sentences: {}", stats.entry_count());
+
+            for (backend, count) in stats.backend_list.iter() {
+                println!("  {}: {}", backend, count);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn format_coverage(coverage: &Coverage) -> String {
+    format!(
+        "{:.1}% of {} tokens ({} excluded)",
+        coverage.coverage * 100.0,
+        coverage.token_count,
+        coverage.excluded_token_count
+    )
+}
+
+pub fn stats(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let vocabulist = open(p)?;
+
+    let stats = vocabulist.stats()?;
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+
+        return Ok(());
+    }
+
+    println!("Tokens: {}", stats.token_count);
+    println!("Expressions: {}", stats.expression_count);
+    println!("Sentences: {}", stats.sentence_count);
+    println!("Documents: {}", stats.document_count);
+    println!();
+    println!("Excluded: {}", stats.excluded_count);
+    println!("In anki: {}", stats.in_anki_count);
+    println!("Learned: {}", stats.learned_count);
+    println!();
+    println!("Known coverage: {}", format_coverage(&stats.coverage));
+
+    for target in stats.target_list.iter() {
+        match target.cards_needed {
+            Some(count) => println!("  {:.0}%: {} more cards", target.target * 100.0, count),
+            None => println!("  {:.0}%: not reachable", target.target * 100.0),
+        }
+    }
+
+    println!();
+    println!("Coverage by document:");
+    for document in stats.document_list.iter() {
+        println!(
+            "  {}: {}",
+            document.name,
+            format_coverage(&document.coverage)
+        );
+    }
+
+    println!();
+    println!("Coverage by pos:");
+    for pos in stats.pos_list.iter() {
+        println!("  {}: {}", pos.name, format_coverage(&pos.coverage));
+    }
+
+    Ok(())
+}
+
+pub fn recommend(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let vocabulist = open(p)?;
+
+    if let Some(name) = m.value_of("document") {
+        let target = number_arg::<f64>(m, "target")? / 100.0;
+
+        let recommendation = vocabulist.recommend(name, target)?;
+        let coverage = &recommendation.coverage;
+
+        println!(
+            "{}: {}, {} unknown",
+            name,
+            format_coverage(coverage),
+            coverage.unknown_token_count()
+        );
+
+        match recommendation.cards_needed {
+            Some(0) => println!("Already at {:.0}% coverage", target * 100.0),
+            Some(count) => {
+                println!(
+                    "Learn {} words to reach {:.0}% coverage:",
+                    count,
+                    target * 100.0
+                );
+
+                for (expression, token_count) in recommendation.expression_list.iter().take(count) {
+                    println!("  {} ({})", expression, token_count);
+                }
+            }
+            None => println!("{:.0}% coverage is not reachable", target * 100.0),
+        }
+
+        return Ok(());
+    }
+
+    let number = number_arg::<i64>(m, "number")?;
+    let difficulty_list = vocabulist.difficulties()?;
+    let len = match number {
+        n if n < 0 => difficulty_list.len(),
+        n => n as usize,
+    };
+
+    println!("Unknown tokens  Unknown words       Document");
+    for difficulty in difficulty_list.iter().take(len) {
+        println!(
+            "{:>13.1}%  {:>6.1}% {:>11}  {}",
+            difficulty.unknown_token_share() * 100.0,
+            difficulty.unknown_expression_share() * 100.0,
+            format!(
+                "({}/{})",
+                difficulty.unknown_expression_count, difficulty.expression_count
+            ),
+            difficulty.name
+        );
+    }
+
+    Ok(())
+}
+
+pub fn mine(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let number = number_arg::<i32>(m, "number")?;
+    let sentence_number = number_arg::<i32>(m, "sentences")?;
+
+    let mut mined_expression_list = vocabulist.mine(sentence_number)?;
+    if number >= 0 {
+        mined_expression_list.truncate(number as usize);
+    }
+
+    if m.is_present("generate") {
+        // each card uses the first i+1 sentence of its expression
+        let card_list: Vec<Card> = mined_expression_list
+            .iter()
+            .map(|x| Card {
+                expression: x.expression.clone(),
+                sentence: x.sentence_list.first().cloned(),
+            })
+            .collect();
+
+        vocabulist.generate_cards(&card_list, &progress_bar::Reporter::default())?;
+
+        return Ok(());
+    }
+
+    for mined_expression in mined_expression_list.iter() {
+        println!(
+            "{} ({})",
+            mined_expression.expression, mined_expression.frequency
+        );
+
+        for sentence in mined_expression.sentence_list.iter() {
+            println!("  {}", sentence);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn keywords(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let name = m.value_of("document").unwrap();
+    let number = number_arg::<i64>(m, "number")?;
+    let min_count = number_arg::<i64>(m, "min-count")?;
+
+    let mut keyword_list = vocabulist.keywords(name, min_count)?;
+    if number >= 0 {
+        keyword_list.truncate(number as usize);
+    }
+
+    if keyword_list.is_empty() {
+        println!("{} has no unknown keywords", name);
+
+        return Ok(());
+    }
+
+    println!("Keyness  Document  Rest  Expression");
+    for keyword in keyword_list.iter() {
+        println!(
+            "{:>7.1}  {:>8}  {:>4}  {}",
+            keyword.log_likelihood, keyword.count, keyword.reference_count, keyword.expression
+        );
+    }
+
+    let word_list: Vec<String> = keyword_list.iter().map(|x| x.expression.clone()).collect();
+
+    if let Some(tag) = m.value_of("tag") {
+        vocabulist.tag(tag, &word_list, true, &())?;
+
+        println!();
+        println!("Tagged {} keywords with {}", word_list.len(), tag);
+    }
+
+    if m.is_present("generate") {
+        let card_list: Vec<Card> = word_list
+            .into_iter()
+            .map(|expression| Card {
+                expression,
+                sentence: None,
+            })
+            .collect();
+
+        vocabulist.generate_cards(&card_list, &progress_bar::Reporter::default())?;
+    }
+
+    Ok(())
+}
+
+pub fn plan(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut vocabulist = open(p)?;
+
+    let name = m.value_of("document").unwrap();
+    let min_count = number_arg::<i64>(m, "min-count")?;
+    let split = match m.value_of("chapter") {
+        Some(chapter) => Split::Chapter(Regex::new(chapter)?),
+        None => Split::Window(number_arg::<i64>(m, "window")?),
+    };
+
+    let chunk_list = vocabulist.plan(name, min_count, &split)?;
+
+    let chunk_number = match m.value_of("chunk") {
+        Some(_) => {
+            let chunk = number_arg::<usize>(m, "chunk")?;
+            if chunk == 0 || chunk > chunk_list.len() {
+                return Err(format!(
+                    "The chunk must be between 1 and {}, the number of chunks of {}",
+                    chunk_list.len(),
+                    name
+                )
+                .into());
+            }
+
+            Some(chunk)
+        }
+        None => None,
+    };
+
+    for (i, chunk) in chunk_list.iter().enumerate() {
+        if chunk_number.is_some() && chunk_number != Some(i + 1) {
+            continue;
+        }
+
+        println!(
+            "{}. {} ({} new words)",
+            i + 1,
+            chunk.title,
+            chunk.expression_list.len()
+        );
+
+        for occurrence in chunk.expression_list.iter() {
+            println!("  {} ({})", occurrence.expression, occurrence.count);
+        }
+    }
+
+    if let (true, Some(chunk_number)) = (m.is_present("generate"), chunk_number) {
+        // each card uses the sentence the expression first appears in
+        let card_list: Vec<Card> = chunk_list[chunk_number - 1]
+            .expression_list
+            .iter()
+            .map(|x| Card {
+                expression: x.expression.clone(),
+                sentence: Some(x.sentence.clone()),
+            })
+            .collect();
+
+        vocabulist.generate_cards(&card_list, &progress_bar::Reporter::default())?;
+    }
+
+    Ok(())
+}
+
+pub fn db(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let merge_policy = p.merge_policy().to_string();
+    let mut vocabulist = open(p)?;
+
+    match m.subcommand() {
+        ("merge", Some(m)) => {
+            let path = Path::new(m.value_of("path").unwrap());
+            let policy = MergePolicy::parse(m.value_of("policy").unwrap_or(&merge_policy))?;
+
+            let summary = vocabulist.merge(path, &policy)?;
+
+            println!("Merged {}", path.display());
+            println!("New documents: {}", summary.document_count);
+            println!("New expressions: {}", summary.expression_count);
+            println!("New sentences: {}", summary.sentence_count);
+            println!("New occurrences: {}", summary.occurrence_count);
+            println!("Resolved conflicts: {}", summary.conflict_count);
+            println!("New tags on expressions: {}", summary.tag_count);
+            println!("Priorities taken: {}", summary.priority_count);
+            println!("New frequency lists: {}", summary.frequency_list_count);
+        }
+        ("backup", Some(m)) => {
+            let path = Path::new(m.value_of("path").unwrap());
+
+            if path.exists() && !m.is_present("force") {
+                return Err(format!(
+                    "{} already exists, use --force to overwrite it.",
+                    path.display()
+                )
+                .into());
+            }
+
+            vocabulist.backup(path, &progress_bar::Reporter::default())?;
+        }
+        ("restore", Some(m)) => {
+            let path = Path::new(m.value_of("path").unwrap());
+
+            let copy_path = vocabulist.restore(path, &progress_bar::Reporter::default())?;
+
+            println!(
+                "The database before the restore was saved to {}",
+                copy_path.display()
+            );
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub fn config(_: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    // the config of the active profile
+    let config_root = config_root()?;
+    let name = profile::active(&config_root, m.value_of("profile"))?;
+    let config_directory = profile::directory(&config_root, &name);
+    let config_file = profile::config_file(&config_root, &name);
+
+    match config_file.is_file() {
+        true => {
+            match m.is_present("force") {
+                true => {
+                    // create the config file
+                    println!(
+                        "Creating new configuration file at {}",
+                        config_file.display()
+                    );
+
+                    // create the directory
+                    if !config_directory.is_dir() {
+                        fs::create_dir_all(&config_directory)?;
+                    }
+
+                    let config = match m.is_present("homebrew") {
+                        true => Config::homebrew(config_directory),
+                        false => Config::default(config_directory),
+                    };
+
+                    let toml = toml::to_string(&config)?;
+                    fs::write(config_file, &toml)?;
+                }
+                false => {
+                    println!("Configuration file already exists");
+                    println!("Doing nothing");
+                    println!();
+                    println!("If you would like to overwrite the file run `vocabulist_rs config --force`")
+                }
+            }
+        }
+        false => {
+            // create the config file
+            println!(
+                "Creating new configuration file at {}",
+                config_file.display()
+            );
+
+            // create the directory
+            if !config_directory.is_dir() {
+                fs::create_dir_all(&config_directory)?;
+            }
+
+            let config = match m.is_present("homebrew") {
+                true => Config::homebrew(config_directory),
+                false => Config::default(config_directory),
+            };
+
+            let toml = toml::to_string(&config)?;
+            fs::write(config_file, &toml)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn profile(_: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let config_root = config_root()?;
+    let active = profile::active(&config_root, m.value_of("profile"))?;
+
+    match m.subcommand() {
+        ("create", Some(m)) => {
+            let name = m.value_of("name").unwrap();
+            profile::validate(name)?;
+
+            let config_file = profile::config_file(&config_root, name);
+            if config_file.is_file() {
+                return Err(format!("The profile {} already exists", name).into());
+            }
+
+            let directory = profile::directory(&config_root, name);
+            fs::create_dir_all(&directory)?;
+
+            // a copy keeps the anki and tokenizer settings but not the database
+            let config = match m.is_present("copy") {
+                true => {
+                    let toml = fs::read_to_string(profile::config_file(&config_root, &active))?;
+                    toml::from_str::<Config>(&toml)?.in_directory(directory)
+                }
+                false => Config::default(directory),
+            };
+
+            fs::write(&config_file, toml::to_string(&config)?)?;
+
+            println!("Created the profile {} at {}", name, config_file.display());
+            println!("Edit it to set the anki deck and tokenizer of the profile");
+        }
+        ("switch", Some(m)) => {
+            let name = m.value_of("name").unwrap();
+            profile::switch(&config_root, name)?;
+
+            println!("Switched to the profile {}", name);
+
+            if let Ok(name) = std::env::var(profile::ENVIRONMENT_VARIABLE) {
+                println!(
+                    "{} is set to {}, which takes precedence",
+                    profile::ENVIRONMENT_VARIABLE,
+                    name
+                );
+            }
+        }
+        _ => {
+            for name in profile::list(&config_root)? {
+                match name == active {
+                    true => println!("* {}", name),
+                    false => println!("  {}", name),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use unicode_width::UnicodeWidthChar;
+
+const HIGHLIGHT_START: &str = "\x1b[1;31m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+
+const PLAIN_START: &str = "【";
+const PLAIN_END: &str = "】";
+
+/// Find every match of the strings in needle_list in sentence.
+///
+/// Returns non-overlapping byte ranges ordered by their position in the sentence.
+pub fn match_list(sentence: &str, needle_list: &[String]) -> Vec<(usize, usize)> {
+    let mut match_list: Vec<(usize, usize)> = Vec::new();
+    for needle in needle_list.iter().filter(|x| !x.is_empty()) {
+        for (start, matched) in sentence.match_indices(needle.as_str()) {
+            match_list.push((start, start + matched.len()));
+        }
+    }
+
+    // prefer the longest match when two matches start at the same position
+    match_list.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut end = 0;
+    match_list
+        .into_iter()
+        .filter(|(start, stop)| {
+            let is_overlapping = *start < end;
+            if !is_overlapping {
+                end = *stop;
+            }
+
+            !is_overlapping
+        })
+        .collect()
+}
+
+/// Keep the end of string that fits in width columns.
+fn truncate_left(string: &str, width: usize) -> (String, usize) {
+    let mut used = 0;
+    let mut char_list: Vec<char> = Vec::new();
+    for c in string.chars().rev() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            break;
+        }
+
+        used += char_width;
+        char_list.push(c);
+    }
+
+    (char_list.into_iter().rev().collect(), used)
+}
+
+/// Keep the start of string that fits in width columns.
+fn truncate_right(string: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut truncated = String::new();
+    for c in string.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            break;
+        }
+
+        used += char_width;
+        truncated.push(c);
+    }
+
+    truncated
+}
+
+/// Format a keyword-in-context line with the match centered between width columns of context.
+///
+/// # Arguments
+///
+/// * `sentence` - The sentence containing the match
+/// * `range` - The byte range of the match in the sentence
+/// * `width` - The number of columns of context on each side
+/// * `is_highlighted` - Whether to highlight the match with terminal colors instead of brackets
+pub fn kwic_line(
+    sentence: &str,
+    range: (usize, usize),
+    width: usize,
+    is_highlighted: bool,
+) -> String {
+    let (start, end) = range;
+    let (left, left_width) = truncate_left(&sentence[..start], width);
+    let right = truncate_right(&sentence[end..], width);
+
+    let (highlight_start, highlight_end) = match is_highlighted {
+        true => (HIGHLIGHT_START, HIGHLIGHT_END),
+        false => (PLAIN_START, PLAIN_END),
+    };
+
+    format!(
+        "{}{}{}{}{}{}",
+        " ".repeat(width - left_width),
+        left,
+        highlight_start,
+        &sentence[start..end],
+        highlight_end,
+        right
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_list() {
+        let sentence = "猫が猫を見た";
+        let needle_list = vec!["猫".to_string(), "猫を".to_string()];
+
+        assert_eq!(match_list(sentence, &needle_list), vec![(0, 3), (6, 12)]);
+    }
+
+    #[test]
+    fn test_kwic_line() {
+        let sentence = "今日は東京に行った";
+        let range = match_list(sentence, &["東京".to_string()])[0];
+
+        assert_eq!(kwic_line(sentence, range, 4, false), "日は【東京】に行");
+        assert_eq!(
+            kwic_line(sentence, range, 8, false),
+            "  今日は【東京】に行った"
+        );
+    }
+}
@@ -1,15 +1,27 @@
+pub mod profile;
+
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 const DATABASE: &str = "vocabulist_rs.db";
 const DICTIONARY: &str = "jmdict.db";
+const CACHE: &str = "tokenizer_cache.db";
+const LEARNED_INTERVAL: i64 = 21;
+const MERGE_POLICY: &str = "learned-wins";
+const RECENCY_HALF_LIFE: f64 = 30.0;
 
 #[derive(Default, Debug, Deserialize, Serialize)]
 pub struct Config {
     database_path: PathBuf,
     dictionary_path: Option<PathBuf>,
+    cache_path: Option<PathBuf>,
+    merge_policy: Option<String>,
     backend: String,
     anki: AnkiConnect,
+    exclusion_rules: Option<Vec<ExclusionRule>>,
+    queries: Option<BTreeMap<String, String>>,
+    scoring: Option<Scoring>,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -21,6 +33,32 @@ pub struct AnkiConnect {
     audio: bool,
     fields: Vec<Vec<String>>,
     tags: Vec<String>,
+    learned_interval: Option<i64>,
+}
+
+/// The weight of each term of the score that orders expressions for generate.
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct Scoring {
+    frequency: Option<f64>,
+    documents: Option<f64>,
+    dispersion: Option<f64>,
+    rank: Option<f64>,
+    recency: Option<f64>,
+    priority: Option<f64>,
+    recency_half_life: Option<f64>,
+}
+
+/// A rule that excludes the expressions matching every condition that is set.
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct ExclusionRule {
+    name: Option<String>,
+    regex: Option<String>,
+    script: Option<String>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pos: Option<Vec<String>>,
+    min_frequency: Option<i64>,
+    max_frequency: Option<i64>,
 }
 
 impl Config {
@@ -33,6 +71,11 @@ impl Config {
         Config {
             database_path,
             dictionary_path,
+            cache_path: None,
+            merge_policy: None,
+            exclusion_rules: None,
+            queries: None,
+            scoring: None,
             backend,
             anki,
         }
@@ -66,6 +109,11 @@ impl Config {
         Config {
             database_path: database_path,
             dictionary_path: None,
+            cache_path: None,
+            merge_policy: None,
+            exclusion_rules: None,
+            queries: None,
+            scoring: None,
             anki: anki,
             backend: backend,
         }
@@ -99,11 +147,25 @@ impl Config {
         Config {
             database_path: database_path,
             dictionary_path: None,
+            cache_path: None,
+            merge_policy: None,
+            exclusion_rules: None,
+            queries: None,
+            scoring: None,
             anki: anki,
             backend: backend,
         }
     }
 
+    /// Keep the database and the tokenizer cache in directory, for a copy of the config in another profile.
+    pub fn in_directory(self, directory: PathBuf) -> Config {
+        Config {
+            database_path: directory.join(DATABASE),
+            cache_path: None,
+            ..self
+        }
+    }
+
     pub fn database_path(&self) -> &PathBuf {
         &self.database_path
     }
@@ -117,6 +179,21 @@ impl Config {
         }
     }
 
+    pub fn cache_path(&self) -> PathBuf {
+        match &self.cache_path {
+            Some(path) => path.clone(),
+            None => self.database_path.with_file_name(CACHE), // store the cache next to the database
+        }
+    }
+
+    /// how `db merge` resolves the flags of expressions that are in both databases
+    pub fn merge_policy(&self) -> &str {
+        match &self.merge_policy {
+            Some(policy) => policy,
+            None => MERGE_POLICY,
+        }
+    }
+
     pub fn anki(&self) -> &AnkiConnect {
         &self.anki
     }
@@ -124,6 +201,27 @@ impl Config {
     pub fn backend(&self) -> &str {
         &self.backend
     }
+
+    /// the rules that exclude expressions when they are imported
+    pub fn exclusion_rules(&self) -> &[ExclusionRule] {
+        match &self.exclusion_rules {
+            Some(rule_list) => rule_list,
+            None => &[],
+        }
+    }
+
+    /// the weights of the score that orders expressions
+    pub fn scoring(&self) -> Scoring {
+        self.scoring.clone().unwrap_or_default()
+    }
+
+    /// the saved filter query with this name
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.queries
+            .as_ref()
+            .and_then(|query_map| query_map.get(name))
+            .map(|query| query.as_str())
+    }
 }
 
 impl AnkiConnect {
@@ -144,6 +242,7 @@ impl AnkiConnect {
             audio,
             fields,
             tags,
+            learned_interval: None,
         }
     }
 
@@ -174,6 +273,85 @@ impl AnkiConnect {
     pub fn tags(&self) -> &Vec<String> {
         &self.tags
     }
+
+    /// the review interval in days after which a card counts as learned
+    pub fn learned_interval(&self) -> i64 {
+        self.learned_interval.unwrap_or(LEARNED_INTERVAL)
+    }
+}
+
+impl ExclusionRule {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn regex(&self) -> Option<&str> {
+        self.regex.as_deref()
+    }
+
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn min_length(&self) -> Option<usize> {
+        self.min_length
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    pub fn pos(&self) -> &[String] {
+        match &self.pos {
+            Some(pos) => pos,
+            None => &[],
+        }
+    }
+
+    pub fn min_frequency(&self) -> Option<i64> {
+        self.min_frequency
+    }
+
+    pub fn max_frequency(&self) -> Option<i64> {
+        self.max_frequency
+    }
+}
+
+impl Scoring {
+    /// the weight of the corpus frequency
+    pub fn frequency(&self) -> f64 {
+        self.frequency.unwrap_or(1.0)
+    }
+
+    /// the weight of the share of documents an expression occurs in
+    pub fn documents(&self) -> f64 {
+        self.documents.unwrap_or(0.0)
+    }
+
+    /// the weight of how evenly an expression is spread over the documents
+    pub fn dispersion(&self) -> f64 {
+        self.dispersion.unwrap_or(0.0)
+    }
+
+    /// the weight of the rank in the external frequency lists
+    pub fn rank(&self) -> f64 {
+        self.rank.unwrap_or(0.0)
+    }
+
+    /// the weight of how recently an expression was last seen
+    pub fn recency(&self) -> f64 {
+        self.recency.unwrap_or(0.0)
+    }
+
+    /// the weight of the manual priority
+    pub fn priority(&self) -> f64 {
+        self.priority.unwrap_or(1.0)
+    }
+
+    /// the number of days after which the recency of an expression halves
+    pub fn recency_half_life(&self) -> f64 {
+        self.recency_half_life.unwrap_or(RECENCY_HALF_LIFE)
+    }
 }
 
 #[cfg(test)]
@@ -205,4 +383,17 @@ mod tests {
 
         assert_eq!(config.dictionary_path(), dictionary_path);
     }
+
+    #[test]
+    fn test_cache_path_default() {
+        let database_path = PathBuf::from("directory/database.db");
+        let cache_path = PathBuf::from(format!("directory/{}", CACHE));
+        let backend = String::from("mecab");
+
+        let anki: AnkiConnect = Default::default();
+
+        let config = Config::new(database_path, None, backend, anki);
+
+        assert_eq!(config.cache_path(), cache_path);
+    }
 }
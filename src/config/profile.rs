@@ -0,0 +1,145 @@
+use crate::error::{self, Error};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The profile whose config is the config.toml in the configuration directory.
+pub const DEFAULT: &str = "default";
+
+/// The environment variable that selects a profile.
+pub const ENVIRONMENT_VARIABLE: &str = "VOCABULIST_PROFILE";
+
+const PROFILES: &str = "profiles";
+const CONFIG: &str = "config.toml";
+const CURRENT: &str = "profile";
+
+/// Check that a profile name can be used as a directory name.
+pub fn validate(name: &str) -> Result<(), Error> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+
+    match is_valid {
+        true => Ok(()),
+        false => Err(Error::new(
+            error::Kind::Config,
+            format!(
+                "Invalid profile name: {}, use letters, numbers, - and _",
+                name
+            ),
+        )),
+    }
+}
+
+/// The directory of a profile, which holds its config and by default its database.
+pub fn directory(config_directory: &Path, name: &str) -> PathBuf {
+    match name {
+        DEFAULT => config_directory.to_path_buf(),
+        name => config_directory.join(PROFILES).join(name),
+    }
+}
+
+/// The config file of a profile.
+pub fn config_file(config_directory: &Path, name: &str) -> PathBuf {
+    directory(config_directory, name).join(CONFIG)
+}
+
+/// Get the active profile.
+///
+/// The profile given as an argument comes first, then the environment variable,
+/// then the profile set with `profile switch`, and otherwise the default profile.
+pub fn active(config_directory: &Path, argument: Option<&str>) -> Result<String, Error> {
+    let name = match argument {
+        Some(name) => name.to_string(),
+        None => match env::var(ENVIRONMENT_VARIABLE) {
+            Ok(name) if !name.is_empty() => name,
+            _ => match fs::read_to_string(config_directory.join(CURRENT)) {
+                Ok(name) if !name.trim().is_empty() => name.trim().to_string(),
+                _ => DEFAULT.to_string(),
+            },
+        },
+    };
+
+    validate(&name)?;
+
+    Ok(name)
+}
+
+/// Get the names of the profiles that have a config file, the default profile first.
+pub fn list(config_directory: &Path) -> Result<Vec<String>, Error> {
+    let mut name_list: Vec<String> = Vec::new();
+
+    let profiles_directory = config_directory.join(PROFILES);
+    if profiles_directory.is_dir() {
+        for entry in fs::read_dir(profiles_directory)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+
+            if validate(&name).is_ok() && config_file(config_directory, &name).is_file() {
+                name_list.push(name);
+            }
+        }
+    }
+
+    name_list.sort();
+
+    if config_file(config_directory, DEFAULT).is_file() {
+        name_list.insert(0, DEFAULT.to_string());
+    }
+
+    Ok(name_list)
+}
+
+/// Make the profile the active profile when neither an argument nor the environment variable selects one.
+pub fn switch(config_directory: &Path, name: &str) -> Result<(), Error> {
+    validate(name)?;
+
+    if !config_file(config_directory, name).is_file() {
+        return Err(Error::new(
+            error::Kind::Config,
+            format!(
+                "The profile {} does not exist, create it with `vocabulist_rs profile create {}`",
+                name, name
+            ),
+        ));
+    }
+
+    fs::write(config_directory.join(CURRENT), name)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let config_directory =
+            env::temp_dir().join(format!("vocabulist_profile_{}", std::process::id()));
+        fs::create_dir_all(directory(&config_directory, "medical")).unwrap();
+        fs::write(config_file(&config_directory, DEFAULT), "").unwrap();
+        fs::write(config_file(&config_directory, "medical"), "").unwrap();
+        fs::create_dir_all(directory(&config_directory, "empty")).unwrap();
+
+        assert_eq!(
+            config_file(&config_directory, "medical"),
+            config_directory.join("profiles/medical/config.toml")
+        );
+        assert_eq!(list(&config_directory).unwrap(), vec!["default", "medical"]);
+
+        assert_eq!(
+            active(&config_directory, Some("general")).unwrap(),
+            "general"
+        );
+        assert!(active(&config_directory, Some("../other")).is_err());
+
+        assert!(switch(&config_directory, "empty").is_err());
+        switch(&config_directory, "medical").unwrap();
+        if env::var(ENVIRONMENT_VARIABLE).is_err() {
+            assert_eq!(active(&config_directory, None).unwrap(), "medical");
+        }
+
+        fs::remove_dir_all(&config_directory).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use rusqlite::types::Value;
+use std::error::Error;
+
+/// The status of an expression, selected with status:NAME.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    New,
+    Learned,
+    Anki,
+    Excluded,
+    Known,
+    Any,
+}
+
+impl Status {
+    pub fn parse(string: &str) -> Result<Status, Box<dyn Error>> {
+        match string {
+            "new" => Ok(Status::New),
+            "learned" => Ok(Status::Learned),
+            "anki" => Ok(Status::Anki),
+            "excluded" => Ok(Status::Excluded),
+            "known" => Ok(Status::Known),
+            "any" => Ok(Status::Any),
+            _ => Err(format!("Unknown status: {}", string).into()),
+        }
+    }
+
+    fn condition(&self) -> &'static str {
+        match self {
+            Status::New => "(is_excluded = 0 AND in_anki = 0 AND is_learned = 0)",
+            Status::Learned => "is_learned = 1",
+            Status::Anki => "in_anki = 1",
+            Status::Excluded => "is_excluded = 1",
+            Status::Known => "(is_learned = 1 OR in_anki = 1)",
+            Status::Any => "1",
+        }
+    }
+}
+
+/// A single condition of a filter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The expression contains the text.
+    Text(String),
+    Pos(String),
+    Tag(String),
+    /// The expression occurs in the document with this name or path.
+    Source(String),
+    Status(Status),
+    /// The frequency compared with the operator.
+    Frequency(String, i64),
+    /// The best rank in the external frequency lists compared with the operator.
+    Rank(String, i64),
+    /// The last time the expression was seen, a unix timestamp, compared with the operator.
+    LastSeen(String, i64),
+}
+
+impl Condition {
+    fn to_sql(&self, parameter_list: &mut Vec<Value>) -> String {
+        match self {
+            Condition::Text(text) => {
+                parameter_list.push(Value::Text(text.to_string()));
+                "instr(expression, ?) > 0".to_string()
+            }
+            Condition::Pos(pos) => {
+                parameter_list.push(Value::Text(pos.to_string()));
+                "id IN (SELECT expression_id FROM occurrences JOIN pos ON pos.id = pos_id WHERE pos = ?)".to_string()
+            }
+            Condition::Tag(tag) => {
+                parameter_list.push(Value::Text(tag.to_string()));
+                "id IN (SELECT expression_id FROM expressions_tags JOIN tags ON tags.id = tag_id WHERE tag = ?)".to_string()
+            }
+            Condition::Source(source) => {
+                parameter_list.push(Value::Text(source.to_string()));
+                parameter_list.push(Value::Text(source.to_string()));
+                "id IN (SELECT expression_id FROM occurrences JOIN document_sentences ON document_sentences.sentence_id = occurrences.sentence_id JOIN documents ON documents.id = document_id WHERE name = ? OR path = ?)".to_string()
+            }
+            Condition::Status(status) => status.condition().to_string(),
+            Condition::Frequency(operator, frequency) => {
+                parameter_list.push(Value::Integer(*frequency));
+                format!("frequency {} ?", operator)
+            }
+            Condition::Rank(operator, rank) => {
+                parameter_list.push(Value::Integer(*rank));
+                format!("rank {} ?", operator)
+            }
+            Condition::LastSeen(operator, timestamp) => {
+                parameter_list.push(Value::Integer(*timestamp));
+                format!("last_seen {} ?", operator)
+            }
+        }
+    }
+}
+
+/// A condition of a filter, negated conditions select the expressions that do not match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub is_negated: bool,
+    pub condition: Condition,
+}
+
+/// Which expressions to select.
+///
+/// Filters are written as space separated terms, for example `pos:動詞 freq>=5 source:"Book 3" -tag:names status:new`.
+/// Every term must match, a term starting with `-` must not match, and words without a key match expressions that contain them.
+///
+/// Without a status term expressions that are in anki, excluded or learned are skipped unless the matching flag is set.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    in_anki: bool,
+    is_excluded: bool,
+    is_learned: bool,
+    term_list: Vec<Term>,
+}
+
+const OPERATOR_LIST: [&str; 6] = [">=", "<=", "!=", ">", "<", "="];
+
+/// Split a query at whitespace, keeping quoted values together.
+fn split_query(query: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut word_list: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut is_quoted = false;
+    let mut is_word = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                is_quoted = !is_quoted;
+                is_word = true;
+            }
+            c if c.is_whitespace() && !is_quoted => {
+                if is_word {
+                    word_list.push(word.clone());
+                    word.clear();
+                    is_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                is_word = true;
+            }
+        }
+    }
+
+    if is_quoted {
+        return Err(format!("Unterminated quote in query: {}", query).into());
+    }
+
+    if is_word {
+        word_list.push(word);
+    }
+
+    Ok(word_list)
+}
+
+fn parse_term(word: &str) -> Result<Term, Box<dyn Error>> {
+    let (is_negated, word) = match word.strip_prefix('-') {
+        Some(word) if !word.is_empty() => (true, word),
+        _ => (false, word),
+    };
+
+    for key in ["freq", "rank"].iter() {
+        let value = match word.strip_prefix(key) {
+            Some(value) => match value.strip_prefix(':') {
+                Some(value) => format!("={}", value),
+                None => value.to_string(),
+            },
+            None => continue,
+        };
+
+        if let Some(operator) = OPERATOR_LIST.iter().find(|x| value.starts_with(*x)) {
+            let number = value[operator.len()..]
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid number in query: {}", word))?;
+
+            let condition = match *key {
+                "freq" => Condition::Frequency(operator.to_string(), number),
+                _ => Condition::Rank(operator.to_string(), number),
+            };
+
+            return Ok(Term {
+                is_negated,
+                condition,
+            });
+        }
+    }
+
+    let condition = match word.find(':') {
+        Some(i) => {
+            let value = word[i + 1..].to_string();
+            if value.is_empty() {
+                return Err(format!("Missing value in query: {}", word).into());
+            }
+
+            match &word[..i] {
+                "pos" => Condition::Pos(value),
+                "tag" => Condition::Tag(value),
+                "source" => Condition::Source(value),
+                "status" => Condition::Status(Status::parse(&value)?),
+                key => return Err(format!("Unknown key in query: {}", key).into()),
+            }
+        }
+        None => Condition::Text(word.to_string()),
+    };
+
+    Ok(Term {
+        is_negated,
+        condition,
+    })
+}
+
+impl Filter {
+    pub fn new(in_anki: bool, is_excluded: bool, is_learned: bool) -> Filter {
+        Filter {
+            in_anki,
+            is_excluded,
+            is_learned,
+            term_list: Vec::new(),
+        }
+    }
+
+    /// Add the terms of the query to the filter.
+    pub fn parse(self, query: &str) -> Result<Filter, Box<dyn Error>> {
+        let mut term_list = self.term_list;
+        for word in split_query(query)? {
+            term_list.push(parse_term(&word)?);
+        }
+
+        Ok(Filter { term_list, ..self })
+    }
+
+    /// Require every tag in tag_list.
+    pub fn tag_list(self, tag_list: Vec<String>) -> Filter {
+        let mut term_list = self.term_list;
+        term_list.extend(tag_list.into_iter().map(|tag| Term {
+            is_negated: false,
+            condition: Condition::Tag(tag),
+        }));
+
+        Filter { term_list, ..self }
+    }
+
+    /// Require expressions seen at or after the unix timestamp.
+    pub fn since(self, since: Option<i64>) -> Filter {
+        let mut term_list = self.term_list;
+        if let Some(since) = since {
+            term_list.push(Term {
+                is_negated: false,
+                condition: Condition::LastSeen(">=".to_string(), since),
+            });
+        }
+
+        Filter { term_list, ..self }
+    }
+
+    /// Create the WHERE clause of the filter and its parameters, the clause is empty if nothing is filtered.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut parameter_list: Vec<Value> = Vec::new();
+        let mut condition_list: Vec<String> = Vec::new();
+
+        // status terms are alternatives, the other terms must all match
+        let status_list: Vec<&str> = self
+            .term_list
+            .iter()
+            .filter_map(|term| match &term.condition {
+                Condition::Status(status) if !term.is_negated => Some(status.condition()),
+                _ => None,
+            })
+            .collect();
+
+        if status_list.is_empty() {
+            if !self.in_anki {
+                condition_list.push("in_anki = 0".to_string());
+            }
+
+            if !self.is_excluded {
+                condition_list.push("is_excluded = 0".to_string());
+            }
+
+            if !self.is_learned {
+                condition_list.push("is_learned = 0".to_string());
+            }
+        } else {
+            condition_list.push(format!("({})", status_list.join(" OR ")));
+        }
+
+        for term in &self.term_list {
+            match (&term.condition, term.is_negated) {
+                (Condition::Status(_), false) => (),
+                (condition, true) => {
+                    condition_list.push(format!("NOT ({})", condition.to_sql(&mut parameter_list)))
+                }
+                (condition, false) => condition_list.push(condition.to_sql(&mut parameter_list)),
+            }
+        }
+
+        match condition_list.is_empty() {
+            true => (String::new(), parameter_list),
+            false => (
+                format!("WHERE {}", condition_list.join(" AND ")),
+                parameter_list,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_query() {
+        assert_eq!(
+            split_query(r#"pos:動詞  source:"Book 3" -tag:names"#).expect("Failed to split"),
+            vec!["pos:動詞", "source:Book 3", "-tag:names"]
+        );
+        assert!(split_query(r#"source:"Book 3"#).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let filter = Filter::default()
+            .parse("freq>=5 -tag:names status:new 猫")
+            .expect("Failed to parse");
+
+        assert_eq!(
+            filter.term_list,
+            vec![
+                Term {
+                    is_negated: false,
+                    condition: Condition::Frequency(">=".to_string(), 5)
+                },
+                Term {
+                    is_negated: true,
+                    condition: Condition::Tag("names".to_string())
+                },
+                Term {
+                    is_negated: false,
+                    condition: Condition::Status(Status::New)
+                },
+                Term {
+                    is_negated: false,
+                    condition: Condition::Text("猫".to_string())
+                },
+            ]
+        );
+
+        assert!(Filter::default().parse("freq>=many").is_err());
+        assert!(Filter::default().parse("rank<=5000").is_ok());
+        assert!(Filter::default().parse("color:red").is_err());
+        assert!(Filter::default().parse("status:unknown").is_err());
+        assert!(Filter::default().parse("pos:").is_err());
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let (query, parameter_list) = Filter::new(true, false, false)
+            .parse("freq:2")
+            .expect("Failed to parse")
+            .to_sql();
+        assert_eq!(
+            query,
+            "WHERE is_excluded = 0 AND is_learned = 0 AND frequency = ?"
+        );
+        assert_eq!(parameter_list, vec![Value::Integer(2)]);
+
+        let (query, parameter_list) = Filter::new(true, true, true).since(Some(86400)).to_sql();
+        assert_eq!(query, "WHERE last_seen >= ?");
+        assert_eq!(parameter_list, vec![Value::Integer(86400)]);
+
+        let (query, _) = Filter::default()
+            .parse("status:learned status:anki -status:excluded")
+            .expect("Failed to parse")
+            .to_sql();
+        assert_eq!(
+            query,
+            "WHERE (is_learned = 1 OR in_anki = 1) AND NOT (is_excluded = 1)"
+        );
+    }
+}
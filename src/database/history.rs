@@ -0,0 +1,499 @@
+use rusqlite::{params, Connection, Transaction};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// A recorded command.
+#[derive(Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub command: String,
+    pub created_at: String,
+    pub change_count: i64,
+    pub is_undone: bool,
+    /// false for commands that changed anki or changed more than the recorded flags and tags
+    pub is_reversible: bool,
+}
+
+/// The entries that were undone, and the entry that cannot be undone that undo stopped at.
+#[derive(Debug, PartialEq)]
+pub struct UndoSummary {
+    pub undone_list: Vec<HistoryEntry>,
+    pub blocking_entry: Option<HistoryEntry>,
+}
+
+/// A change to one column of one row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub table_name: String,
+    pub row_id: i64,
+    pub column_name: String,
+    pub old_value: Option<i64>,
+    pub new_value: Option<i64>,
+}
+
+/// Close the entries that were left open by a command that did not finish.
+pub fn close_open(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "UPDATE history SET is_open = 0 WHERE is_open = 1;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+fn begin(conn: &Connection, command: &str) -> Result<i64, Box<dyn Error>> {
+    close_open(conn)?;
+
+    conn.execute(
+        "INSERT INTO history (command) VALUES (?);",
+        params![command],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+fn select_change_list_for_id(
+    conn: &Connection,
+    id: i64,
+    order: &str,
+) -> Result<Vec<Change>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT table_name, row_id, column_name, old_value, new_value
+                FROM history_changes
+                WHERE history_id = ?
+                ORDER BY id {};",
+        order
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let change_list = statement
+        .query_map(params![id], |row| {
+            Ok(Change {
+                table_name: row.get(0)?,
+                row_id: row.get(1)?,
+                column_name: row.get(2)?,
+                old_value: row.get(3)?,
+                new_value: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<Change>, _>>()?;
+
+    Ok(change_list)
+}
+
+/// Merge the changes to the same column of the same row into one change from the first old value
+/// to the last new value, and drop the changes that end where they started.
+///
+/// The merged changes are ordered by the last change to each column.
+pub fn compact(change_list: &[Change]) -> Vec<Change> {
+    let mut position_map: HashMap<(&str, i64, &str), usize> = HashMap::new();
+    let mut compacted_list: Vec<(usize, Change)> = Vec::new();
+
+    for (i, change) in change_list.iter().enumerate() {
+        let key = (
+            change.table_name.as_str(),
+            change.row_id,
+            change.column_name.as_str(),
+        );
+
+        match position_map.get(&key) {
+            Some(position) => {
+                let entry = &mut compacted_list[*position];
+                entry.0 = i;
+                entry.1.new_value = change.new_value;
+            }
+            None => {
+                position_map.insert(key, compacted_list.len());
+                compacted_list.push((i, change.clone()));
+            }
+        }
+    }
+
+    compacted_list.sort_by_key(|x| x.0);
+
+    compacted_list
+        .into_iter()
+        .map(|x| x.1)
+        .filter(|x| x.old_value != x.new_value)
+        .collect()
+}
+
+fn end(conn: &mut Connection, id: i64) -> Result<(), Box<dyn Error>> {
+    let change_list = compact(&select_change_list_for_id(conn, id, "ASC")?);
+
+    let tx = conn.transaction()?;
+
+    // undoing a change to in_anki would not remove the notes from anki
+    let is_reversible = !change_list.iter().any(|x| x.column_name == "in_anki");
+
+    tx.execute(
+        "UPDATE history SET is_open = 0, is_reversible = ? WHERE id = ?;",
+        params![is_reversible, id],
+    )?;
+    tx.execute(
+        "DELETE FROM history_changes WHERE history_id = ?;",
+        params![id],
+    )?;
+
+    match change_list.is_empty() {
+        true => {
+            tx.execute("DELETE FROM history WHERE id = ?;", params![id])?;
+        }
+        false => {
+            let mut statement = tx.prepare("INSERT INTO history_changes (history_id, table_name, row_id, column_name, old_value, new_value) VALUES (?, ?, ?, ?, ?, ?);")?;
+
+            for change in change_list.iter() {
+                statement.execute(params![
+                    id,
+                    change.table_name,
+                    change.row_id,
+                    change.column_name,
+                    change.old_value,
+                    change.new_value
+                ])?;
+            }
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Run function and record every change it makes under command.
+///
+/// Commands that change nothing are not recorded.
+///
+/// # Arguments
+///
+/// * `conn` - A &mut Connection object
+/// * `command` - The command line to show in the history
+/// * `function` - The function that changes the database
+pub fn record<T>(
+    conn: &mut Connection,
+    command: &str,
+    function: impl FnOnce(&mut Connection) -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let id = begin(conn, command)?;
+
+    let result = function(conn);
+
+    end(conn, id)?;
+
+    result
+}
+
+/// Record command as an entry that cannot be undone, before running it.
+///
+/// This is for commands that change more than the flags and tags, such as imports,
+/// the changes they make are not recorded.
+pub fn record_irreversible(conn: &Connection, command: &str) -> Result<(), Box<dyn Error>> {
+    close_open(conn)?;
+
+    conn.execute(
+        "INSERT INTO history (command, is_open, is_reversible) VALUES (?, 0, 0);",
+        params![command],
+    )?;
+
+    Ok(())
+}
+
+/// Get the most recent entries, newest first.
+///
+/// # Arguments
+///
+/// * `conn` - A &Connection object
+/// * `limit` - The maximum number of entries, -1 for all
+pub fn select_entry_list(
+    conn: &Connection,
+    limit: i32,
+) -> Result<Vec<HistoryEntry>, Box<dyn Error>> {
+    let query = "SELECT history.id, command, datetime(created_at, 'unixepoch', 'localtime'), COUNT(history_changes.id), is_undone, is_reversible
+                FROM history LEFT JOIN history_changes ON history_id = history.id
+                GROUP BY history.id
+                ORDER BY history.id DESC
+                LIMIT ?;";
+
+    let mut statement = conn.prepare(query)?;
+    let entry_list = statement
+        .query_map(params![limit], |row| {
+            Ok(HistoryEntry {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                created_at: row.get(2)?,
+                change_count: row.get(3)?,
+                is_undone: row.get(4)?,
+                is_reversible: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<HistoryEntry>, _>>()?;
+
+    Ok(entry_list)
+}
+
+/// Get the changes of an entry, with the expression, pos or tag they apply to.
+pub fn select_change_list(
+    conn: &Connection,
+    id: i64,
+) -> Result<Vec<(String, Change)>, Box<dyn Error>> {
+    let mut change_list = Vec::new();
+
+    for change in select_change_list_for_id(conn, id, "ASC")? {
+        let query = match change.table_name.as_str() {
+            "pos" => "SELECT pos FROM pos WHERE id = ?;",
+            _ => "SELECT expression FROM expressions WHERE id = ?;",
+        };
+
+        let label: Option<String> = conn
+            .query_row(query, params![change.row_id], |row| row.get(0))
+            .ok();
+
+        change_list.push((
+            label.unwrap_or_else(|| format!("#{}", change.row_id)),
+            change,
+        ));
+    }
+
+    Ok(change_list)
+}
+
+/// Set a column back to value.
+fn revert(tx: &Transaction, change: &Change) -> Result<(), Box<dyn Error>> {
+    let value = change.old_value;
+
+    match (change.table_name.as_str(), change.column_name.as_str()) {
+        ("expressions", "row") => {
+            // only remove an expression that has not been imported since
+            tx.execute(
+                "DELETE FROM expressions WHERE id = ? AND id NOT IN (SELECT expression_id FROM occurrences);",
+                params![change.row_id],
+            )?;
+        }
+        ("expressions_tags", tag) => match value {
+            Some(1) => {
+                tx.execute("INSERT OR IGNORE INTO tags (tag) VALUES (?);", params![tag])?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO expressions_tags (expression_id, tag_id) SELECT ?, id FROM tags WHERE tag = ?;",
+                    params![change.row_id, tag],
+                )?;
+            }
+            _ => {
+                tx.execute(
+                    "DELETE FROM expressions_tags WHERE expression_id = ? AND tag_id IN (SELECT id FROM tags WHERE tag = ?);",
+                    params![change.row_id, tag],
+                )?;
+            }
+        },
+        ("expressions", "is_excluded") => {
+            tx.execute(
+                "UPDATE expressions SET is_excluded = ? WHERE id = ?;",
+                params![value, change.row_id],
+            )?;
+        }
+        ("expressions", "in_anki") => {
+            tx.execute(
+                "UPDATE expressions SET in_anki = ? WHERE id = ?;",
+                params![value, change.row_id],
+            )?;
+        }
+        ("expressions", "is_learned") => {
+            tx.execute(
+                "UPDATE expressions SET is_learned = ? WHERE id = ?;",
+                params![value, change.row_id],
+            )?;
+        }
+        ("expressions", "priority") => {
+            tx.execute(
+                "UPDATE expressions SET priority = ? WHERE id = ?;",
+                params![value, change.row_id],
+            )?;
+        }
+        ("pos", "is_excluded") => {
+            tx.execute(
+                "UPDATE pos SET is_excluded = ? WHERE id = ?;",
+                params![value, change.row_id],
+            )?;
+        }
+        (table_name, column_name) => {
+            return Err(format!("Cannot undo a change to {}.{}", table_name, column_name).into())
+        }
+    }
+
+    Ok(())
+}
+
+/// Undo the last number entries that have not been undone, newest first.
+///
+/// Undo stops at the newest entry that cannot be undone,
+/// reverting an older entry would overwrite what that command decided.
+pub fn undo(conn: &mut Connection, number: i32) -> Result<UndoSummary, Box<dyn Error>> {
+    let mut entry_list: Vec<HistoryEntry> = Vec::new();
+    let mut blocking_entry = None;
+
+    for entry in select_entry_list(conn, -1)?
+        .into_iter()
+        .filter(|x| !x.is_undone)
+        .take(number.max(0) as usize)
+    {
+        if !entry.is_reversible {
+            blocking_entry = Some(entry);
+            break;
+        }
+
+        entry_list.push(entry);
+    }
+
+    let tx = conn.transaction()?;
+
+    for entry in entry_list.iter() {
+        for change in select_change_list_for_id(&tx, entry.id, "DESC")? {
+            revert(&tx, &change)?;
+        }
+
+        tx.execute(
+            "UPDATE history SET is_undone = 1 WHERE id = ?;",
+            params![entry.id],
+        )?;
+    }
+
+    tx.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM expressions_tags);",
+        params![],
+    )?;
+
+    tx.commit()?;
+
+    Ok(UndoSummary {
+        undone_list: entry_list,
+        blocking_entry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(row_id: i64, column_name: &str, old_value: i64, new_value: i64) -> Change {
+        Change {
+            table_name: "expressions".to_string(),
+            row_id,
+            column_name: column_name.to_string(),
+            old_value: Some(old_value),
+            new_value: Some(new_value),
+        }
+    }
+
+    #[test]
+    fn test_compact() {
+        let change_list = vec![
+            change(1, "in_anki", 1, 0),
+            change(2, "in_anki", 1, 0),
+            change(1, "in_anki", 0, 1),
+            change(3, "row", 0, 1),
+            change(3, "is_learned", 0, 1),
+        ];
+
+        assert_eq!(
+            compact(&change_list),
+            vec![
+                change(2, "in_anki", 1, 0),
+                change(3, "row", 0, 1),
+                change(3, "is_learned", 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_undo() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        super::super::migration::migrate(&mut conn, None).unwrap();
+
+        conn.execute(
+            "INSERT INTO expressions (expression, in_anki) VALUES ('猫', 1), ('犬', 1);",
+            params![],
+        )
+        .unwrap();
+
+        let select_flags = |conn: &Connection| -> Vec<(String, i64, i64)> {
+            let mut statement = conn
+                .prepare("SELECT expression, in_anki, is_learned FROM expressions ORDER BY id;")
+                .unwrap();
+            let flag_list = statement
+                .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap()
+                .collect::<Result<Vec<(String, i64, i64)>, _>>()
+                .unwrap();
+
+            flag_list
+        };
+
+        // a sync that finds only one card in anki
+        record(&mut conn, "sync", |conn| {
+            conn.execute("UPDATE expressions SET in_anki = 0;", params![])?;
+            conn.execute(
+                "UPDATE expressions SET in_anki = 1 WHERE expression = '猫';",
+                params![],
+            )?;
+
+            Ok(())
+        })
+        .unwrap();
+
+        // learning a word that has not been imported
+        record(&mut conn, "learn 鳥", |conn| {
+            conn.execute(
+                "INSERT INTO expressions (expression, frequency) VALUES ('鳥', 0);",
+                params![],
+            )?;
+            conn.execute(
+                "UPDATE expressions SET is_learned = 1 WHERE expression = '鳥';",
+                params![],
+            )?;
+
+            Ok(())
+        })
+        .unwrap();
+
+        // commands that change nothing are not recorded
+        record(&mut conn, "list", |_| Ok(())).unwrap();
+
+        // undo stops at the sync that changed anki
+        let summary = undo(&mut conn, 3).unwrap();
+        assert_eq!(summary.undone_list.len(), 1);
+        assert_eq!(summary.undone_list[0].command, "learn 鳥");
+        assert_eq!(summary.blocking_entry.unwrap().command, "sync");
+        assert_eq!(
+            select_flags(&conn),
+            vec![("猫".to_string(), 1, 0), ("犬".to_string(), 0, 0)]
+        );
+
+        // entries are only undone once
+        let summary = undo(&mut conn, 1).unwrap();
+        assert!(summary.undone_list.is_empty());
+        assert_eq!(summary.blocking_entry.unwrap().command, "sync");
+
+        // an import is listed but its changes are not recorded
+        record_irreversible(&conn, "import book.txt").unwrap();
+        conn.execute(
+            "INSERT INTO expressions (expression, frequency) VALUES ('魚', 1);",
+            params![],
+        )
+        .unwrap();
+
+        let entry_list = select_entry_list(&conn, -1).unwrap();
+        assert_eq!(entry_list.len(), 3);
+        assert_eq!(entry_list[0].command, "import book.txt");
+        assert_eq!(entry_list[0].change_count, 0);
+        assert!(!entry_list[0].is_reversible);
+        assert_eq!(entry_list[1].command, "learn 鳥");
+        assert_eq!(entry_list[1].change_count, 2);
+        assert!(entry_list[1].is_reversible);
+        assert!(entry_list[1].is_undone);
+        assert_eq!(entry_list[2].change_count, 1);
+        assert!(!entry_list[2].is_reversible);
+
+        let summary = undo(&mut conn, 1).unwrap();
+        assert!(summary.undone_list.is_empty());
+        assert_eq!(summary.blocking_entry.unwrap().command, "import book.txt");
+    }
+}
@@ -0,0 +1,466 @@
+use super::{migration, query};
+use crate::error;
+use rusqlite::{params, Connection, DatabaseName, OpenFlags, Transaction};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// How to resolve the status flags of an expression that is in both databases.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    /// keep the flags of this database
+    Ours,
+    /// take the flags of the other database
+    Theirs,
+    /// an expression learned in either database is learned and not excluded
+    LearnedWins,
+    /// an expression excluded in either database is excluded and not learned
+    ExcludedWins,
+}
+
+impl MergePolicy {
+    pub fn parse(string: &str) -> Result<MergePolicy, Box<dyn Error>> {
+        match string {
+            "ours" => Ok(MergePolicy::Ours),
+            "theirs" => Ok(MergePolicy::Theirs),
+            "learned-wins" => Ok(MergePolicy::LearnedWins),
+            "excluded-wins" => Ok(MergePolicy::ExcludedWins),
+            _ => Err(error::Error::new(
+                error::Kind::Config,
+                format!("Unknown merge policy: {}", string),
+            )
+            .into()),
+        }
+    }
+
+    /// Combine the flags of an expression that is in both databases.
+    pub fn resolve(&self, ours: Flags, theirs: Flags) -> Flags {
+        let in_anki = ours.in_anki || theirs.in_anki;
+        let is_learned = ours.is_learned || theirs.is_learned;
+        let is_excluded = ours.is_excluded || theirs.is_excluded;
+
+        match self {
+            MergePolicy::Ours => ours,
+            MergePolicy::Theirs => theirs,
+            MergePolicy::LearnedWins => Flags {
+                is_excluded: is_excluded && !is_learned,
+                in_anki,
+                is_learned,
+            },
+            MergePolicy::ExcludedWins => Flags {
+                is_excluded,
+                in_anki,
+                is_learned: is_learned && !is_excluded,
+            },
+        }
+    }
+}
+
+/// The status flags of an expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flags {
+    pub is_excluded: bool,
+    pub in_anki: bool,
+    pub is_learned: bool,
+}
+
+/// The number of rows merged from the other database.
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeSummary {
+    pub document_count: usize,
+    pub expression_count: usize,
+    pub sentence_count: usize,
+    pub occurrence_count: usize,
+    pub conflict_count: usize,
+    /// the tags that were added to expressions
+    pub tag_count: usize,
+    /// the expressions whose priority was taken from the other database
+    pub priority_count: usize,
+    /// the external frequency lists that were added
+    pub frequency_list_count: usize,
+}
+
+const SELECT_OTHER_EXPRESSION_LIST: &str =
+    "SELECT expression, is_excluded, in_anki, is_learned FROM other.expressions;";
+
+const SELECT_FLAGS: &str =
+    "SELECT is_excluded, in_anki, is_learned FROM main.expressions WHERE expression = ?;";
+
+const UPDATE_FLAGS: &str = "UPDATE main.expressions SET is_excluded = ?, in_anki = ?, is_learned = ? WHERE expression = ?;";
+
+const INSERT_EXPRESSION: &str = "INSERT INTO main.expressions (expression, frequency, is_excluded, in_anki, is_learned) VALUES (?, 0, ?, ?, ?);";
+
+const UPDATE_SEEN: &str = "UPDATE main.expressions SET (first_seen, last_seen) = (
+                    SELECT MIN(IFNULL(expressions.first_seen, oe.first_seen), IFNULL(oe.first_seen, expressions.first_seen)),
+                        MAX(IFNULL(expressions.last_seen, oe.last_seen), IFNULL(oe.last_seen, expressions.last_seen))
+                    FROM other.expressions AS oe WHERE oe.expression = expressions.expression
+                )
+                WHERE expression IN (SELECT expression FROM other.expressions);";
+
+const INSERT_POS: &str =
+    "INSERT OR IGNORE INTO main.pos (pos, is_excluded) SELECT pos, is_excluded FROM other.pos;";
+
+const INSERT_SURFACE_STRING: &str = "INSERT OR IGNORE INTO main.surface_strings (surface_string) SELECT surface_string FROM other.surface_strings;";
+
+const INSERT_SENTENCE: &str =
+    "INSERT OR IGNORE INTO main.sentences (sentence) SELECT sentence FROM other.sentences;";
+
+const INSERT_OCCURRENCE: &str = "INSERT OR IGNORE INTO main.occurrences (sentence_id, position, expression_id, pos_id, surface_string_id)
+                SELECT sentences.id, o.position, expressions.id, pos.id, surface_strings.id
+                FROM other.occurrences AS o
+                JOIN other.sentences AS os ON os.id = o.sentence_id
+                JOIN other.expressions AS oe ON oe.id = o.expression_id
+                JOIN other.pos AS op ON op.id = o.pos_id
+                JOIN other.surface_strings AS oss ON oss.id = o.surface_string_id
+                JOIN main.sentences ON sentences.sentence = os.sentence
+                JOIN main.expressions ON expressions.expression = oe.expression
+                JOIN main.pos ON pos.pos = op.pos
+                JOIN main.surface_strings ON surface_strings.surface_string = oss.surface_string;";
+
+const INSERT_DOCUMENT: &str = "INSERT OR IGNORE INTO main.documents (name, path, imported_at) SELECT name, path, imported_at FROM other.documents;";
+
+const INSERT_DOCUMENT_SENTENCE: &str =
+    "INSERT OR IGNORE INTO main.document_sentences (document_id, sentence_id, position)
+                SELECT documents.id, sentences.id, ods.position
+                FROM other.document_sentences AS ods
+                JOIN other.documents AS od ON od.id = ods.document_id
+                JOIN other.sentences AS os ON os.id = ods.sentence_id
+                JOIN main.documents ON documents.path = od.path
+                JOIN main.sentences ON sentences.sentence = os.sentence;";
+
+const INSERT_SENTENCES_FTS: &str = "INSERT INTO main.sentences_fts (rowid, surface_string_list)
+                SELECT sentence_id, group_concat(surface_string, ' ')
+                FROM (
+                    SELECT sentence_id, surface_string
+                    FROM main.occurrences JOIN main.surface_strings ON surface_strings.id = surface_string_id
+                    WHERE sentence_id NOT IN (SELECT rowid FROM main.sentences_fts)
+                    ORDER BY sentence_id ASC, position ASC
+                )
+                GROUP BY sentence_id;";
+
+const INSERT_TAG: &str = "INSERT OR IGNORE INTO main.tags (tag) SELECT tag FROM other.tags;";
+
+const INSERT_EXPRESSION_TAG: &str =
+    "INSERT OR IGNORE INTO main.expressions_tags (expression_id, tag_id)
+                SELECT expressions.id, tags.id
+                FROM other.expressions_tags AS oet
+                JOIN other.expressions AS oe ON oe.id = oet.expression_id
+                JOIN other.tags AS ot ON ot.id = oet.tag_id
+                JOIN main.expressions ON expressions.expression = oe.expression
+                JOIN main.tags ON tags.tag = ot.tag;";
+
+/// Take the priority of the other database where ours has none, or everywhere when theirs wins.
+const UPDATE_PRIORITY: &str = "UPDATE main.expressions SET priority = (
+                    SELECT oe.priority FROM other.expressions AS oe WHERE oe.expression = expressions.expression
+                )
+                WHERE (priority = 0 OR ?) AND EXISTS (
+                    SELECT 1 FROM other.expressions AS oe
+                    WHERE oe.expression = expressions.expression AND oe.priority != 0 AND oe.priority != expressions.priority
+                );";
+
+const INSERT_FREQUENCY_LIST: &str = "INSERT OR IGNORE INTO main.frequency_lists (name, imported_at) SELECT name, imported_at FROM other.frequency_lists;";
+
+const INSERT_EXTERNAL_FREQUENCY: &str =
+    "INSERT OR IGNORE INTO main.external_frequencies (frequency_list_id, expression, reading, rank)
+                SELECT frequency_lists.id, oef.expression, oef.reading, oef.rank
+                FROM other.external_frequencies AS oef
+                JOIN other.frequency_lists AS ofl ON ofl.id = oef.frequency_list_id
+                JOIN main.frequency_lists ON frequency_lists.name = ofl.name;";
+
+/// Copy the other database and bring the copy up to the latest schema,
+/// so the other database is never modified.
+fn open_other_copy(path: &Path, copy_path: &Path) -> Result<Connection, Box<dyn Error>> {
+    let other = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    other.backup(DatabaseName::Main, copy_path, None)?;
+
+    let mut conn = Connection::open(copy_path)?;
+    migration::migrate(&mut conn, None)?;
+
+    Ok(conn)
+}
+
+fn merge_expression_list(
+    tx: &Transaction,
+    policy: &MergePolicy,
+    summary: &mut MergeSummary,
+) -> Result<(), Box<dyn Error>> {
+    let mut statement = tx.prepare(SELECT_OTHER_EXPRESSION_LIST)?;
+    let other_list = statement
+        .query_map(params![], |row| {
+            let flags = Flags {
+                is_excluded: row.get::<_, Option<bool>>(1)?.unwrap_or(false),
+                in_anki: row.get(2)?,
+                is_learned: row.get(3)?,
+            };
+
+            Ok((row.get::<_, String>(0)?, flags))
+        })?
+        .collect::<Result<Vec<(String, Flags)>, _>>()?;
+
+    for (expression, theirs) in other_list.iter() {
+        let ours = tx
+            .prepare_cached(SELECT_FLAGS)?
+            .query_map(params![expression], |row| {
+                Ok(Flags {
+                    is_excluded: row.get::<_, Option<bool>>(0)?.unwrap_or(false),
+                    in_anki: row.get(1)?,
+                    is_learned: row.get(2)?,
+                })
+            })?
+            .next()
+            .transpose()?;
+
+        match ours {
+            Some(ours) => {
+                let flags = policy.resolve(ours, *theirs);
+
+                if flags != ours {
+                    summary.conflict_count += 1;
+                    tx.prepare_cached(UPDATE_FLAGS)?.execute(params![
+                        flags.is_excluded,
+                        flags.in_anki,
+                        flags.is_learned,
+                        expression
+                    ])?;
+                }
+            }
+            None => {
+                summary.expression_count += 1;
+                tx.prepare_cached(INSERT_EXPRESSION)?.execute(params![
+                    expression,
+                    theirs.is_excluded,
+                    theirs.in_anki,
+                    theirs.is_learned
+                ])?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge the database at path into conn.
+///
+/// Documents, sentences and occurrences are unioned, so sentences imported on both machines are only counted once,
+/// and frequencies are recomputed from the merged occurrences.
+/// Tags and external frequency lists are unioned by name, and an expression takes the priority of the other database
+/// unless it has its own, or the policy is theirs.
+///
+/// # Arguments
+///
+/// * `conn` - A &mut Connection object
+/// * `path` - The path to the database to merge into conn
+/// * `policy` - How to resolve the flags of expressions that are in both databases
+pub fn merge(
+    conn: &mut Connection,
+    path: &Path,
+    policy: &MergePolicy,
+) -> Result<MergeSummary, Box<dyn Error>> {
+    let copy_path =
+        std::env::temp_dir().join(format!("vocabulist_merge_{}.db", std::process::id()));
+
+    let result = open_other_copy(path, &copy_path).and_then(|other| {
+        drop(other);
+        merge_attached(conn, &copy_path, policy)
+    });
+
+    let _ = fs::remove_file(&copy_path);
+
+    result
+}
+
+fn merge_attached(
+    conn: &mut Connection,
+    copy_path: &Path,
+    policy: &MergePolicy,
+) -> Result<MergeSummary, Box<dyn Error>> {
+    conn.execute(
+        "ATTACH DATABASE ? AS other;",
+        params![copy_path.to_string_lossy()],
+    )?;
+
+    let result = (|| {
+        let mut summary = MergeSummary::default();
+        let tx = conn.transaction()?;
+
+        merge_expression_list(&tx, policy, &mut summary)?;
+        tx.execute(UPDATE_SEEN, params![])?;
+
+        tx.execute(INSERT_POS, params![])?;
+        tx.execute(INSERT_SURFACE_STRING, params![])?;
+        summary.sentence_count = tx.execute(INSERT_SENTENCE, params![])?;
+        summary.occurrence_count = tx.execute(INSERT_OCCURRENCE, params![])?;
+        tx.execute(INSERT_SENTENCES_FTS, params![])?;
+        summary.document_count = tx.execute(INSERT_DOCUMENT, params![])?;
+        tx.execute(INSERT_DOCUMENT_SENTENCE, params![])?;
+
+        tx.execute(INSERT_TAG, params![])?;
+        summary.tag_count = tx.execute(INSERT_EXPRESSION_TAG, params![])?;
+        summary.priority_count =
+            tx.execute(UPDATE_PRIORITY, params![*policy == MergePolicy::Theirs])?;
+        summary.frequency_list_count = tx.execute(INSERT_FREQUENCY_LIST, params![])?;
+        tx.execute(INSERT_EXTERNAL_FREQUENCY, params![])?;
+
+        query::expression::update_frequency_from_occurrences(&tx)?;
+
+        tx.commit()?;
+
+        Ok(summary)
+    })();
+
+    conn.execute("DETACH DATABASE other;", params![])?;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(is_excluded: bool, in_anki: bool, is_learned: bool) -> Flags {
+        Flags {
+            is_excluded,
+            in_anki,
+            is_learned,
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let ours = flags(true, false, false);
+        let theirs = flags(false, true, true);
+
+        assert_eq!(MergePolicy::Ours.resolve(ours, theirs), ours);
+        assert_eq!(MergePolicy::Theirs.resolve(ours, theirs), theirs);
+        assert_eq!(
+            MergePolicy::LearnedWins.resolve(ours, theirs),
+            flags(false, true, true)
+        );
+        assert_eq!(
+            MergePolicy::ExcludedWins.resolve(ours, theirs),
+            flags(true, true, false)
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let directory = std::env::temp_dir();
+        let ours_path = directory.join(format!("vocabulist_merge_ours_{}.db", std::process::id()));
+        let theirs_path =
+            directory.join(format!("vocabulist_merge_theirs_{}.db", std::process::id()));
+
+        let mut ours = super::super::connect(&ours_path).unwrap();
+        let mut theirs = super::super::connect(&theirs_path).unwrap();
+
+        let expression = |expression: &str, sentence: &str, position: usize| {
+            crate::Expression::new(expression.to_string())
+                .pos(vec!["名詞".to_string()])
+                .sentence(vec![sentence.to_string()])
+                .surface_string(vec![expression.to_string()])
+                .position(position)
+        };
+
+        super::super::insert_expression_list(
+            &mut ours,
+            vec![expression("猫", "猫と犬", 0), expression("犬", "猫と犬", 2)],
+            &|| {},
+        )
+        .unwrap();
+        super::super::insert_expression_list(
+            &mut theirs,
+            vec![
+                expression("猫", "猫と犬", 0),
+                expression("犬", "猫と犬", 2),
+                expression("猫", "猫だ", 0),
+                expression("鳥", "鳥だ", 0),
+            ],
+            &|| {},
+        )
+        .unwrap();
+        theirs
+            .execute(
+                "UPDATE expressions SET is_learned = 1 WHERE expression = '猫';",
+                params![],
+            )
+            .unwrap();
+
+        // the tag ids and frequency list ids of the two databases differ
+        ours.execute_batch(
+            "INSERT INTO tags (tag) VALUES ('仕事');
+            UPDATE expressions SET priority = 1 WHERE expression = '猫';",
+        )
+        .unwrap();
+        theirs
+            .execute_batch(
+                "INSERT INTO tags (tag) VALUES ('動物');
+                INSERT INTO expressions_tags (expression_id, tag_id)
+                    SELECT expressions.id, tags.id FROM expressions, tags WHERE expression IN ('猫', '鳥');
+                UPDATE expressions SET priority = 3 WHERE expression IN ('猫', '犬');
+                INSERT INTO frequency_lists (name) VALUES ('jpdb');
+                INSERT INTO external_frequencies (frequency_list_id, expression, rank) VALUES (1, '猫', 10);",
+            )
+            .unwrap();
+        drop(theirs);
+
+        let summary = merge(&mut ours, &theirs_path, &MergePolicy::LearnedWins).unwrap();
+
+        assert_eq!(
+            summary,
+            MergeSummary {
+                document_count: 0,
+                expression_count: 1,
+                sentence_count: 2,
+                occurrence_count: 2,
+                conflict_count: 1,
+                tag_count: 2,
+                priority_count: 1,
+                frequency_list_count: 1,
+            }
+        );
+
+        let tag_list = super::super::select_tag_list(&ours).unwrap();
+        assert_eq!(
+            tag_list,
+            vec![("仕事".to_string(), 0), ("動物".to_string(), 2)]
+        );
+
+        // ours keeps its own priority
+        let priority_list: Vec<(String, i64)> = ours
+            .prepare("SELECT expression, priority FROM expressions ORDER BY id;")
+            .unwrap()
+            .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            priority_list,
+            vec![
+                ("猫".to_string(), 1),
+                ("犬".to_string(), 3),
+                ("鳥".to_string(), 0)
+            ]
+        );
+
+        let rank: i64 = ours
+            .query_row(
+                "SELECT rank FROM external_frequencies JOIN frequency_lists ON frequency_lists.id = frequency_list_id WHERE name = 'jpdb' AND expression = '猫';",
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(rank, 10);
+
+        let frequency: i64 = ours
+            .query_row(
+                "SELECT frequency FROM expressions WHERE expression = '猫' AND is_learned = 1;",
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(frequency, 2);
+
+        drop(ours);
+        fs::remove_file(&ours_path).unwrap();
+        fs::remove_file(&theirs_path).unwrap();
+    }
+}
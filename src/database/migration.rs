@@ -0,0 +1,301 @@
+use super::query;
+use crate::error;
+use rusqlite::{params, Connection, Transaction};
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single step that moves the schema from one version to the next.
+type Migration = fn(&Transaction) -> Result<(), Box<dyn Error>>;
+
+/// The ordered list of migrations.
+///
+/// The schema version stored in `PRAGMA user_version` is the number of migrations that have been
+/// applied, so new steps must only ever be appended to the end of this list.
+const MIGRATION_LIST: &[Migration] = &[
+    create_initial_tables,
+    create_occurrences,
+    create_sentences_fts,
+    create_documents,
+    create_tags,
+    create_history,
+    create_frequency_lists,
+    create_priority,
+    create_seen,
+    create_history_reversible,
+    create_learned_by_sync,
+];
+
+/// The schema version written by this build.
+pub fn latest_version() -> i32 {
+    MIGRATION_LIST.len() as i32
+}
+
+/// Get the schema version of the database.
+pub fn version(conn: &Connection) -> Result<i32, Box<dyn Error>> {
+    let version = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    Ok(version)
+}
+
+/// Create the path of the copy taken before migrating from version.
+pub fn backup_path(path: &Path, version: i32) -> PathBuf {
+    let mut backup_path: OsString = path.as_os_str().to_owned();
+    backup_path.push(format!(".v{}.bak", version));
+
+    PathBuf::from(backup_path)
+}
+
+/// Bring the database up to the latest schema version.
+///
+/// # Arguments
+///
+/// * `conn` - A &mut Connection object
+/// * `path` - The path to the database file, a copy is taken here before migrating an existing database
+pub fn migrate(conn: &mut Connection, path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let current_version = version(conn)?;
+    let latest_version = latest_version();
+
+    if current_version > latest_version {
+        return Err(error::Error::new(
+            error::Kind::Database,
+            format!(
+                "The database was written by a newer version of vocabulist (schema version {}, this version supports up to {}). Please upgrade vocabulist.",
+                current_version, latest_version
+            ),
+        )
+        .into());
+    }
+
+    if current_version == latest_version {
+        return Ok(());
+    }
+
+    if let Some(path) = path {
+        fs::copy(path, backup_path(path, current_version))?;
+    }
+
+    let tx = conn.transaction()?;
+
+    for migration in MIGRATION_LIST[current_version as usize..].iter() {
+        migration(&tx)?;
+    }
+
+    tx.pragma_update(None, "user_version", &latest_version)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/* Migrations */
+
+/// Version 1: the tables that existed before the schema was versioned.
+fn create_initial_tables(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_expressions(tx)?;
+    query::table::create_pos(tx)?;
+    query::table::create_sentences(tx)?;
+    query::table::create_surface_strings(tx)?;
+    query::table::create_expressions_pos_sentences_surface_strings(tx)?;
+
+    Ok(())
+}
+
+/// Version 2: store every token as an occurrence keyed by sentence and position,
+/// and derive the frequency of each expression from its occurrences.
+///
+/// Token positions were not recorded before this version,
+/// so each distinct row of the old join table becomes one occurrence.
+fn create_occurrences(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_occurrences(tx)?;
+
+    tx.execute(
+        "INSERT INTO occurrences (sentence_id, position, expression_id, pos_id, surface_string_id)
+                SELECT sentence_id,
+                    ROW_NUMBER() OVER (PARTITION BY sentence_id ORDER BY rowid) - 1,
+                    expression_id,
+                    pos_id,
+                    surface_string_id
+                FROM expressions_pos_sentences_surface_strings;",
+        params![],
+    )?;
+
+    query::table::create_expressions_pos_sentences_surface_strings_view(tx)?;
+    query::expression::update_frequency_from_occurrences(tx)?;
+
+    Ok(())
+}
+
+/// Version 3: a full-text index over the surface strings of every sentence.
+fn create_sentences_fts(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_sentences_fts(tx)?;
+
+    tx.execute(
+        "INSERT INTO sentences_fts (rowid, surface_string_list)
+                SELECT sentence_id, group_concat(surface_string, ' ')
+                FROM (
+                    SELECT sentence_id, surface_string
+                    FROM occurrences JOIN surface_strings ON surface_strings.id = surface_string_id
+                    ORDER BY sentence_id ASC, position ASC
+                )
+                GROUP BY sentence_id;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Version 4: the imported documents and the sentences they contain.
+///
+/// Files imported before this version are not recorded, importing them again links their sentences.
+fn create_documents(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_documents(tx)?;
+    query::table::create_document_sentences(tx)?;
+
+    Ok(())
+}
+
+/// Version 5: user-defined tags on expressions.
+fn create_tags(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_tags(tx)?;
+    query::table::create_expressions_tags(tx)?;
+
+    Ok(())
+}
+
+/// Version 6: the history of state-changing commands, recorded by triggers.
+fn create_history(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_history(tx)?;
+    query::table::create_history_triggers(tx)?;
+
+    Ok(())
+}
+
+/// Version 7: external frequency lists with the rank of each lemma and reading.
+fn create_frequency_lists(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_frequency_lists(tx)?;
+    query::table::create_external_frequencies(tx)?;
+
+    Ok(())
+}
+
+/// Version 8: a manual priority on expressions, recorded in the history.
+fn create_priority(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_expressions_priority(tx)?;
+    query::table::create_history_column_trigger(tx, "expressions", "priority")?;
+
+    Ok(())
+}
+
+/// Version 9: the first and last time each expression was seen,
+/// filled in from the import times of the documents it occurs in.
+fn create_seen(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_expressions_seen(tx)?;
+    query::expression::update_seen_from_documents(tx)?;
+
+    Ok(())
+}
+
+/// Version 10: history entries that cannot be undone,
+/// because they changed anki or changed more than the recorded flags and tags.
+///
+/// Entries recorded before this version that changed anki are marked as well.
+fn create_history_reversible(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_history_reversible(tx)?;
+
+    tx.execute(
+        "UPDATE history SET is_reversible = 0
+                WHERE id IN (SELECT history_id FROM history_changes WHERE column_name = 'in_anki');",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Version 11: expressions that were marked as learned by a sync rather than with `learn`.
+///
+/// Expressions that a sync marked as learned before this version count as learned with `learn`.
+fn create_learned_by_sync(tx: &Transaction) -> Result<(), Box<dyn Error>> {
+    query::table::create_expressions_learned_by_sync(tx)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_new_database() {
+        let mut conn = Connection::open_in_memory().expect("Failed to open database");
+
+        migrate(&mut conn, None).expect("Failed to migrate database");
+
+        assert_eq!(version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_migrate_newer_database() {
+        let mut conn = Connection::open_in_memory().expect("Failed to open database");
+        conn.pragma_update(None, "user_version", &(latest_version() + 1))
+            .unwrap();
+
+        assert!(migrate(&mut conn, None).is_err());
+    }
+
+    /// assert that rows of the old join table become occurrences with recounted frequencies
+    #[test]
+    fn test_migrate_occurrences() {
+        let mut conn = Connection::open_in_memory().expect("Failed to open database");
+
+        {
+            let tx = conn.transaction().unwrap();
+            create_initial_tables(&tx).unwrap();
+            tx.pragma_update(None, "user_version", &1).unwrap();
+            tx.execute_batch(
+                "INSERT INTO expressions (id, expression, frequency) VALUES (1, '猫', 7);
+                INSERT INTO pos (id, pos) VALUES (1, '名詞');
+                INSERT INTO sentences (id, sentence) VALUES (1, '猫だ'), (2, '猫と犬');
+                INSERT INTO surface_strings (id, surface_string) VALUES (1, '猫');
+                INSERT INTO expressions_pos_sentences_surface_strings VALUES (1, 1, 1, 1), (1, 2, 1, 1);",
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        migrate(&mut conn, None).expect("Failed to migrate database");
+
+        let frequency: i64 = conn
+            .query_row(
+                "SELECT frequency FROM expressions WHERE id = 1;",
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM expressions_pos_sentences_surface_strings;",
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(frequency, 2);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_migrate_backup() {
+        let path =
+            std::env::temp_dir().join(format!("vocabulist_migrate_{}.db", std::process::id()));
+        let backup_path = backup_path(&path, 0);
+
+        let mut conn = Connection::open(&path).expect("Failed to open database");
+        migrate(&mut conn, Some(&path)).expect("Failed to migrate database");
+
+        assert!(backup_path.is_file());
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup_path).unwrap();
+    }
+}
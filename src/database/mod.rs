@@ -1,7 +1,17 @@
+use crate::error::{self, Context};
+use crate::exclusion;
+use crate::frequency_list::FrequencyEntry;
 use crate::Expression;
-use rusqlite::{params, Connection};
+use filter::Filter;
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OpenFlags, Transaction};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 #[macro_use]
 /// sql!(update_in_anki_for_expression, query, params=[tx: &Transaction, expression: &str, in_anki: bool])
@@ -27,90 +37,208 @@ macro_rules! sql {
     }
 }
 
+pub mod filter;
+pub mod history;
+pub mod merge;
+mod migration;
 mod query;
+pub mod stats;
 
-/// Setup the database.
+const BACKUP_STEP_PAGE_COUNT: i32 = 100;
+
+/// Open a connection to the database and bring its schema up to date.
+///
+/// An existing database is copied next to itself before it is migrated,
+/// and a database written by a newer version of vocabulist is refused.
 ///
 /// # Arguments
 ///
-/// * `conn` - A &Connection object
+/// * `path` - An &str with the file system path to the database
+pub fn connect(path: &PathBuf) -> Result<Connection, Box<dyn Error>> {
+    let message = format!("Failed to open {}", path.display());
+    let is_existing =
+        path.is_file() && path.metadata().context(error::Kind::Io, &message)?.len() > 0;
+
+    let mut conn = Connection::open(path).context(error::Kind::Database, &message)?;
 
-fn initialize(conn: &Connection) -> Result<(), Box<dyn Error>> {
-    query::table::create_expressions(conn)?;
-    query::table::create_pos(conn)?;
-    query::table::create_sentences(conn)?;
-    query::table::create_surface_strings(conn)?;
-    query::table::create_expressions_pos_sentences_surface_strings(conn)?;
+    match is_existing {
+        true => migration::migrate(&mut conn, Some(path)),
+        false => migration::migrate(&mut conn, None),
+    }
+    .context(
+        error::Kind::Database,
+        &format!("Failed to migrate {}", path.display()),
+    )?;
+
+    history::close_open(&conn).context(error::Kind::Database, &message)?;
+
+    Ok(conn)
+}
+
+/// Copy pages until the backup is done, retrying while the source database is busy.
+///
+/// The callback is called with the number of copied pages and the total number of pages after each step.
+fn run_backup(backup: &Backup, callback: &dyn Fn(i32, i32)) -> Result<(), Box<dyn Error>> {
+    loop {
+        let result = backup.step(BACKUP_STEP_PAGE_COUNT)?;
+
+        let progress = backup.progress();
+        callback(progress.pagecount - progress.remaining, progress.pagecount);
+
+        match result {
+            StepResult::Done => break,
+            StepResult::More => {}
+            _ => thread::sleep(Duration::from_millis(100)), // busy or locked, retry the step
+        }
+    }
 
     Ok(())
 }
 
-/// Open a connection to the database.
+/// Copy the database to path with the online backup API, so it is safe to run while the database is in use.
 ///
 /// # Arguments
 ///
-/// * `path` - An &str with the file system path to the database
+/// * `conn` - A &Connection object
+/// * `path` - The path to write the copy to
+/// * `callback` - Called with the number of copied pages and the total number of pages
+pub fn backup(
+    conn: &Connection,
+    path: &Path,
+    callback: &dyn Fn(i32, i32),
+) -> Result<(), Box<dyn Error>> {
+    let mut destination = Connection::open(path)?;
+    let backup = Backup::new(conn, &mut destination)?;
 
-pub fn connect(path: &PathBuf) -> Connection {
-    let conn = Connection::open(path).expect("Failed to connect to the database");
-    initialize(&conn).expect("Failed to initialize database");
+    run_backup(&backup, callback)
+}
+
+/// Create the path of the copy taken before restoring a backup over the database.
+pub fn restore_backup_path(database_path: &Path) -> PathBuf {
+    let mut backup_path: OsString = database_path.as_os_str().to_owned();
+    backup_path.push(".restore.bak");
 
-    conn
+    PathBuf::from(backup_path)
+}
+
+/// Replace the contents of the database with a backup taken by `backup`.
+///
+/// A backup written by a newer version of vocabulist is refused,
+/// and a backup written by an older version is migrated after it is restored.
+/// The database is copied to `restore_backup_path` first, like a migration copies it before changing it.
+///
+/// # Arguments
+///
+/// * `conn` - A &mut Connection object
+/// * `database_path` - The path to the database file
+/// * `path` - The path to the backup
+/// * `callback` - Called with the number of copied pages and the total number of pages
+pub fn restore(
+    conn: &mut Connection,
+    database_path: &Path,
+    path: &Path,
+    callback: &dyn Fn(i32, i32),
+) -> Result<(), Box<dyn Error>> {
+    if !path.is_file() {
+        return Err(error::Error::new(
+            error::Kind::Io,
+            format!("The backup {} does not exist.", path.display()),
+        )
+        .into());
+    }
+
+    let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let version = migration::version(&source)?;
+    if version > migration::latest_version() {
+        return Err(error::Error::new(
+            error::Kind::Database,
+            format!(
+                "The backup was written by a newer version of vocabulist (schema version {}). Please upgrade vocabulist.",
+                version
+            ),
+        )
+        .into());
+    }
+
+    backup(conn, &restore_backup_path(database_path), &|_, _| {})?;
+
+    {
+        let backup = Backup::new(&source, conn)?;
+        run_backup(&backup, callback)?;
+    }
+
+    migration::migrate(conn, None)
 }
 
 /// Create a list of Expression objects with sentences that have not been inserted into the database.
 ///
 /// # Arguments
 ///
-/// * `conn` - A &Connection object
-/// * `sentence_list` - A Vec<String> of sentences that have already been imported
+/// * `sentence_set` - A HashSet<String> of sentences that have already been imported
 /// * `expression_list` - A list of Expression objects
-
 pub fn filter_imported_expression_list(
-    sentence_list: &Vec<String>,
+    sentence_set: &HashSet<String>,
     expression_list: Vec<Expression>,
 ) -> Vec<Expression> {
-    let mut tmp_expression_list: Vec<Expression> = Vec::new();
-    for expression in expression_list.into_iter() {
-        let mut is_duplicate = false;
-        for sentence in sentence_list.iter() {
-            let sentence_string = &expression.get_sentence()[0];
-            if sentence_string == sentence {
-                is_duplicate = true;
-                break;
-            }
-        }
-
-        if !is_duplicate {
-            tmp_expression_list.push(expression);
-        }
-    }
-
-    tmp_expression_list
+    expression_list
+        .into_iter()
+        .filter(|x| !sentence_set.contains(&x.get_sentence()[0]))
+        .collect()
 }
 
-/// Create a list of sentences that have already been imported and that are in sentence_list.
+/// Create a set of sentences that have already been imported and that are in sentence_list.
 pub fn select_imported_sentence_list(
     conn: &Connection,
-    sentence_list: &Vec<String>,
-) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut duplicate_sentence_list: Vec<String> = Vec::new();
+    sentence_list: &[String],
+) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut duplicate_sentence_set: HashSet<String> = HashSet::new();
     for sentence in sentence_list.iter() {
-        if query::sentence::exists(conn, sentence)? {
-            duplicate_sentence_list.push(sentence.to_string());
+        if !duplicate_sentence_set.contains(sentence) && query::sentence::exists(conn, sentence)? {
+            duplicate_sentence_set.insert(sentence.to_string());
         }
     }
 
-    Ok(duplicate_sentence_list)
+    Ok(duplicate_sentence_set)
 }
+
+/// Look up the id of a row in map, then in the database, and insert the row if it does not exist.
+fn select_or_insert_id(
+    tx: &Transaction,
+    map: &mut HashMap<String, i64>,
+    string: &str,
+    select_id: SelectId,
+    insert: Insert,
+) -> Result<i64, Box<dyn Error>> {
+    if let Some(id) = map.get(string) {
+        return Ok(*id);
+    }
+
+    let id = match select_id(tx, string)? {
+        Some(id) => id,
+        None => insert(tx, string)?,
+    };
+
+    map.insert(string.to_string(), id);
+
+    Ok(id)
+}
+
+type SelectId = fn(&Transaction, &str) -> Result<Option<i64>, Box<dyn Error>>;
+type Insert = fn(&Transaction, &str) -> Result<i64, Box<dyn Error>>;
+
 /// Insert a vector of Expression objects into the database.
 ///
+/// Ids are cached in memory for the duration of the import so each distinct
+/// expression, pos, sentence and surface string is only looked up once.
+/// Only occurrences that were not already stored are counted,
+/// and frequencies and the full-text index are written once at the end of the same transaction.
+///
 /// # Arguments
 ///
 /// * `conn` - A &Connection object
 /// * `expression_list` - The Expression objects to add to the database
 /// * `callback` - A function that is called after each expression is inserted
-
 pub fn insert_expression_list(
     conn: &mut Connection,
     expression_list: Vec<Expression>,
@@ -118,25 +246,72 @@ pub fn insert_expression_list(
 ) -> Result<(), Box<dyn Error>> {
     let tx = conn.transaction()?;
 
+    let mut expression_map: HashMap<String, i64> = HashMap::new();
+    let mut pos_map: HashMap<String, i64> = HashMap::new();
+    let mut sentence_map: HashMap<String, i64> = HashMap::new();
+    let mut surface_string_map: HashMap<String, i64> = HashMap::new();
+
+    let mut frequency_map: HashMap<i64, i64> = HashMap::new();
+    let mut sentence_set: HashSet<i64> = HashSet::new();
+
     for expression in expression_list.iter() {
         let expression_string = expression.get_expression();
         let pos_string = &expression.get_pos()[0];
         let sentence_string = &expression.get_sentence()[0];
         let surface_string = &expression.get_surface_string()[0];
 
-        query::expression::insert(&tx, expression_string)?;
-        query::pos::insert(&tx, pos_string)?;
-        query::sentence::insert(&tx, sentence_string)?;
-        query::surface_string::insert(&tx, surface_string)?;
+        let expression_id = select_or_insert_id(
+            &tx,
+            &mut expression_map,
+            expression_string,
+            query::expression::select_id,
+            query::expression::insert,
+        )?;
+        let pos_id = select_or_insert_id(
+            &tx,
+            &mut pos_map,
+            pos_string,
+            query::pos::select_id,
+            query::pos::insert,
+        )?;
+        let sentence_id = select_or_insert_id(
+            &tx,
+            &mut sentence_map,
+            sentence_string,
+            query::sentence::select_id,
+            query::sentence::insert,
+        )?;
+        let surface_string_id = select_or_insert_id(
+            &tx,
+            &mut surface_string_map,
+            surface_string,
+            query::surface_string::select_id,
+            query::surface_string::insert,
+        )?;
+
+        let is_new = query::occurrence::insert(
+            &tx,
+            sentence_id,
+            expression.get_position() as i64,
+            expression_id,
+            pos_id,
+            surface_string_id,
+        )?;
+
+        if is_new {
+            *frequency_map.entry(expression_id).or_insert(0) += 1;
+            sentence_set.insert(sentence_id);
+        }
 
-        let expression_id = query::expression::select_id(&tx, expression_string)?;
-        let pos_id = query::pos::select_id(&tx, pos_string)?;
-        let sentence_id = query::sentence::select_id(&tx, sentence_string)?;
-        let surface_string_id = query::surface_string::select_id(&tx, surface_string)?;
+        callback();
+    }
 
-        query::insert_join(&tx, expression_id, pos_id, sentence_id, surface_string_id)?;
+    for (expression_id, count) in frequency_map.iter() {
+        query::expression::update_frequency(&tx, *expression_id, *count)?;
+    }
 
-        callback();
+    for sentence_id in sentence_set.iter() {
+        query::sentence::update_fts(&tx, *sentence_id)?;
     }
 
     tx.commit()?;
@@ -144,96 +319,222 @@ pub fn insert_expression_list(
     Ok(())
 }
 
-fn create_select_query(
-    in_anki: bool,
-    is_excluded: bool,
-    is_learned: bool,
-    order_by: &str,
-    is_asc: bool,
-    max: i32,
-) -> String {
-    let mut query = "SELECT expression FROM expressions ".to_string();
-
-    if !(in_anki && is_excluded && is_learned) {
-        query.push_str("WHERE ");
-
-        if !in_anki {
-            query.push_str("in_anki = 0 ");
-
-            if !is_excluded || !is_learned {
-                query.push_str("AND ");
-            }
-        }
+/// Record a document and link it to its sentences.
+///
+/// Importing the same path again replaces the sentences of the document,
+/// so an updated file is tracked with its current contents.
+///
+/// # Arguments
+///
+/// * `conn` - A &mut Connection object
+/// * `name` - The name of the document
+/// * `path` - The path the document was imported from
+/// * `sentence_list` - The sentences of the document in order, sentences that were not imported are skipped
+pub fn insert_document(
+    conn: &mut Connection,
+    name: &str,
+    path: &str,
+    sentence_list: &[String],
+    seen_at: i64,
+) -> Result<(), Box<dyn Error>> {
+    let tx = conn.transaction()?;
 
-        if !is_excluded {
-            query.push_str("is_excluded = 0 ");
+    let document_id = match query::document::select_id(&tx, path)? {
+        Some(id) => {
+            query::document::update_imported_at(&tx, id)?;
+            query::document::delete_sentence_list(&tx, id)?;
 
-            if !is_learned {
-                query.push_str("AND ");
-            }
+            id
         }
+        None => query::document::insert(&tx, name, path)?,
+    };
 
-        if !is_learned {
-            query.push_str("is_learned = 0 ");
+    for (position, sentence) in sentence_list.iter().enumerate() {
+        if let Some(sentence_id) = query::sentence::select_id(&tx, sentence)? {
+            query::document::insert_sentence(&tx, document_id, sentence_id, position)?;
         }
     }
 
-    query.push_str("ORDER BY ");
+    query::expression::update_seen_for_document(&tx, document_id, seen_at)?;
 
-    match order_by {
-        "id" => query.push_str("id "),
-        "expression" => query.push_str("expression "),
-        _ => query.push_str("frequency "),
-    }
+    tx.commit()?;
 
-    match is_asc {
-        true => query.push_str("ASC "),
-        false => query.push_str("DESC "),
-    }
+    Ok(())
+}
 
-    if max > -1 {
-        query.push_str(&format!("LIMIT {}", max));
+/// Find the id of a document by its name or path.
+///
+/// # Arguments
+///
+/// * `conn` - A &Connection object
+/// * `name` - The file name or the full path the document was imported from
+pub fn select_document_id(conn: &Connection, name: &str) -> Result<i64, Box<dyn Error>> {
+    let id_list = query::document::select_id_list_for_name(conn, name)?;
+
+    match id_list.as_slice() {
+        [id] => Ok(*id),
+        [] => Err(format!("The document {} has not been imported.", name).into()),
+        _ => Err(format!(
+            "More than one document is named {}, use the full path instead.",
+            name
+        )
+        .into()),
     }
-
-    query
 }
 
-/// Get a list of expressions for the given parameters
+/// Recompute the frequency of every expression from its stored occurrences.
 ///
+/// Returns the number of expressions whose frequency was wrong.
+pub fn recount(conn: &mut Connection) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let count = query::expression::update_frequency_from_occurrences(&tx)?;
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// The expressions with their best rank in the external frequency lists,
+/// and their rank by corpus frequency and the mean of both ranks.
 ///
+/// Expressions that are in no list are ranked after every listed word when the ranks are combined.
 ///
+/// The dispersion of an expression is measured over the documents, with p the share of the tokens of a document that are the expression.
+/// dispersion_variation is the squared coefficient of variation of p divided by the number of documents minus one,
+/// which is 0 for an expression that is spread evenly and 1 for an expression that occurs in a single document of equal sized documents,
+/// so Juilland's D is 1 minus its square root.
+const RANKED_EXPRESSIONS: &str = "WITH document_sizes AS (
+                SELECT document_id, COUNT(*) AS size
+                FROM document_sentences
+                JOIN occurrences ON occurrences.sentence_id = document_sentences.sentence_id
+                GROUP BY document_id
+            ),
+            ranked_expressions AS (
+                SELECT expressions.*, r.rank,
+                    RANK() OVER (ORDER BY frequency DESC) AS corpus_rank,
+                    (RANK() OVER (ORDER BY frequency DESC)
+                        + IFNULL(r.rank, (SELECT IFNULL(MAX(rank), 0) + 1 FROM external_frequencies))) / 2.0 AS combined_rank,
+                    IFNULL(d.document_count, 0) AS document_count,
+                    IFNULL(frequency * 1000000.0 / (SELECT NULLIF(COUNT(*), 0) FROM occurrences), 0.0) AS per_million,
+                    CASE WHEN n.count > 1 AND d.s1 > 0
+                        THEN (n.count * d.s2 / (d.s1 * d.s1) - 1) / (n.count - 1)
+                    END AS dispersion_variation
+                FROM expressions LEFT JOIN (
+                    SELECT expression, MIN(rank) AS rank FROM external_frequencies GROUP BY expression
+                ) AS r ON r.expression = expressions.expression
+                LEFT JOIN (
+                    SELECT expression_id, COUNT(*) AS document_count,
+                        SUM(count * 1.0 / size) AS s1, SUM((count * 1.0 / size) * (count * 1.0 / size)) AS s2
+                    FROM (
+                        SELECT expression_id, document_id, COUNT(*) AS count
+                        FROM occurrences
+                        JOIN document_sentences ON document_sentences.sentence_id = occurrences.sentence_id
+                        GROUP BY expression_id, document_id
+                    ) JOIN document_sizes USING (document_id)
+                    GROUP BY expression_id
+                ) AS d ON d.expression_id = expressions.id
+                CROSS JOIN (SELECT COUNT(*) AS count FROM document_sizes) AS n
+            )";
+
+/// Create the query for the filter and its parameters, the limit is the last parameter.
 ///
-pub fn select_expression_list(
-    conn: &Connection,
-    in_anki: bool,
-    is_excluded: bool,
-    is_learned: bool,
+/// Ordering by rank puts the most frequent words first unless is_asc is set, like ordering by frequency,
+/// ordering by recent puts the most recently seen words first, expressions that were never seen come last,
+/// and ordering by dispersion puts the most evenly spread words first.
+fn create_select_query(
+    filter: &Filter,
     order_by: &str,
     is_asc: bool,
     limit: i32,
-) -> Result<Vec<Expression>, Box<dyn Error>> {
-    let query = create_select_query(in_anki, is_excluded, is_learned, order_by, is_asc, limit);
+) -> (String, Vec<Value>) {
+    let (condition, mut parameter_list) = filter.to_sql();
+
+    let (order_by, is_rank) = match order_by {
+        "id" => ("id", false),
+        "expression" => ("expression", false),
+        "rank" => ("rank IS NULL, rank", true),
+        "combined" => ("combined_rank", true),
+        "recent" => ("last_seen IS NULL, last_seen", false),
+        "range" => ("document_count", false),
+        "dispersion" => ("dispersion_variation IS NULL, dispersion_variation", true),
+        _ => ("frequency", false),
+    };
 
-    let mut select_expression = conn.prepare(&query)?;
+    let direction = match is_asc != is_rank {
+        true => "ASC",
+        false => "DESC",
+    };
 
-    let tmp_list = select_expression.query_map(params![], |row| {
-        let expression: String = row.get(0)?;
-        Ok(Expression::new(expression))
-    })?;
+    let query = format!(
+        "{} SELECT expression, id, frequency, is_excluded, in_anki, is_learned, rank, document_count, last_seen, priority, per_million, dispersion_variation FROM ranked_expressions {} ORDER BY {} {} LIMIT ?",
+        RANKED_EXPRESSIONS, condition, order_by, direction
+    );
+    parameter_list.push(Value::Integer(limit as i64));
 
-    let mut expression_list: Vec<Expression> = Vec::new();
-    for expression in tmp_list {
-        if let Ok(expression) = expression {
-            expression_list.push(expression);
-        }
-    }
+    (query, parameter_list)
+}
 
-    Ok(expression_list)
+/// An expression with its frequency and status flags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionRecord {
+    pub id: i64,
+    pub expression: String,
+    pub frequency: i64,
+    pub is_excluded: bool,
+    pub in_anki: bool,
+    pub is_learned: bool,
+    /// the best rank in the external frequency lists
+    pub rank: Option<i64>,
+    /// the number of documents the expression occurs in
+    pub document_count: i64,
+    /// the last time the expression was seen in an imported document
+    pub last_seen: Option<i64>,
+    pub priority: i64,
+    /// the frequency per million tokens of the corpus
+    pub per_million: f64,
+    /// Juilland's D over the documents, from 0 for an expression in a single document to 1 for an evenly spread expression,
+    /// None with fewer than two documents or for an expression that is in none of them
+    pub dispersion: Option<f64>,
+}
+
+/// Get a list of expression records for the given parameters
+pub fn select_expression_record_list(
+    conn: &Connection,
+    filter: &Filter,
+    order_by: &str,
+    is_asc: bool,
+    limit: i32,
+) -> Result<Vec<ExpressionRecord>, Box<dyn Error>> {
+    let (query, parameter_list) = create_select_query(filter, order_by, is_asc, limit);
+
+    let mut statement = conn.prepare(&query)?;
+
+    let record_list = statement
+        .query_map(parameter_list, |row| {
+            Ok(ExpressionRecord {
+                expression: row.get(0)?,
+                id: row.get(1)?,
+                frequency: row.get(2)?,
+                is_excluded: row.get(3)?,
+                in_anki: row.get(4)?,
+                is_learned: row.get(5)?,
+                rank: row.get(6)?,
+                document_count: row.get(7)?,
+                last_seen: row.get(8)?,
+                priority: row.get(9)?,
+                per_million: row.get(10)?,
+                dispersion: row
+                    .get::<_, Option<f64>>(11)?
+                    .map(|x| (1.0 - x.max(0.0).sqrt()).max(0.0)),
+            })
+        })?
+        .collect::<Result<Vec<ExpressionRecord>, _>>()?;
+
+    Ok(record_list)
 }
 
 pub fn update_is_excluded_for_expression_list(
     conn: &mut Connection,
-    expression_list: &Vec<Expression>,
+    expression_list: &[Expression],
     is_excluded: bool,
     callback: &dyn Fn(),
 ) -> Result<(), Box<dyn Error>> {
@@ -250,6 +551,455 @@ pub fn update_is_excluded_for_expression_list(
     Ok(())
 }
 
+/// Set is_learned for a list of expressions.
+///
+/// Expressions that are marked as learned before they have been imported are added with a frequency of 0,
+/// so they are already known when they show up in an imported text.
+///
+/// Returns the number of expressions that were added.
+pub fn update_is_learned_for_expression_list(
+    conn: &mut Connection,
+    expression_list: &[Expression],
+    is_learned: bool,
+    callback: &dyn Fn(),
+) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    let mut count = 0;
+    for expression in expression_list {
+        let expression_string = expression.get_expression();
+
+        if is_learned && query::expression::select_id(&tx, expression_string)?.is_none() {
+            query::expression::insert(&tx, expression_string)?;
+            count += 1;
+        }
+
+        query::expression::update_is_learned(&tx, expression_string, is_learned)?;
+        callback();
+    }
+
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// Add or remove a tag on a list of expressions.
+///
+/// Expressions that have not been imported are skipped, and a tag that is removed from its last expression is deleted.
+///
+/// Returns the number of expressions that were not found.
+pub fn update_tag_for_expression_list(
+    conn: &mut Connection,
+    tag: &str,
+    expression_list: &[Expression],
+    is_tagged: bool,
+    callback: &dyn Fn(),
+) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    let tag_id = match (query::tag::select_id(&tx, tag)?, is_tagged) {
+        (Some(id), _) => id,
+        (None, true) => query::tag::insert(&tx, tag)?,
+        (None, false) => return Err(format!("The tag {} does not exist.", tag).into()),
+    };
+
+    let mut count = 0;
+    for expression in expression_list {
+        match query::expression::select_id(&tx, expression.get_expression())? {
+            Some(expression_id) => match is_tagged {
+                true => query::tag::insert_expression(&tx, tag_id, expression_id)?,
+                false => query::tag::delete_expression(&tx, tag_id, expression_id)?,
+            },
+            None => {
+                count += 1;
+                false
+            }
+        };
+
+        callback();
+    }
+
+    query::tag::delete_unused(&tx)?;
+
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// Get every tag with the number of expressions that have it.
+pub fn select_tag_list(conn: &Connection) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    query::tag::select_list(conn)
+}
+
+/// Get the tags of an expression.
+pub fn select_tag_for_expression(
+    conn: &Connection,
+    expression: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    query::tag::select_list_for_expression(conn, expression)
+}
+
+/// Import an external frequency list, replacing the entries of a list with the same name.
+///
+/// Returns the number of entries in the list.
+pub fn insert_frequency_list(
+    conn: &mut Connection,
+    name: &str,
+    entry_list: &[FrequencyEntry],
+    callback: &dyn Fn(),
+) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    let id = query::frequency::insert_list(&tx, name)?;
+    query::frequency::delete_entry_list(&tx, id)?;
+
+    for entry in entry_list {
+        query::frequency::insert_entry(&tx, id, &entry.expression, &entry.reading, entry.rank)?;
+        callback();
+    }
+
+    let count = tx.query_row(
+        "SELECT COUNT(*) FROM external_frequencies WHERE frequency_list_id = ?;",
+        params![id],
+        |row| row.get::<_, i64>(0),
+    )?;
+
+    tx.commit()?;
+
+    Ok(count as usize)
+}
+
+/// Delete an external frequency list, returns false if there is no list with this name.
+pub fn delete_frequency_list(conn: &mut Connection, name: &str) -> Result<bool, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+    let is_deleted = query::frequency::delete_list(&tx, name)?;
+    tx.commit()?;
+
+    Ok(is_deleted)
+}
+
+/// Get every external frequency list with its number of entries.
+pub fn select_frequency_list_list(conn: &Connection) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    query::frequency::select_list(conn)
+}
+
+/// Set the manual priority for a list of expressions.
+///
+/// Returns the number of expressions that were skipped because they have not been imported.
+pub fn update_priority_for_expression_list(
+    conn: &mut Connection,
+    expression_list: &[Expression],
+    priority: i64,
+    callback: &dyn Fn(),
+) -> Result<usize, Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    let mut count = 0;
+    for expression in expression_list {
+        if !query::expression::update_priority(&tx, expression.get_expression(), priority)? {
+            count += 1;
+        }
+
+        callback();
+    }
+
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// The largest values in the database that the terms of a score are scaled by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreScale {
+    pub max_frequency: i64,
+    pub document_count: i64,
+    pub max_rank: i64,
+    pub max_priority: i64,
+}
+
+/// Select the largest values that the terms of a score are scaled by.
+pub fn select_score_scale(conn: &Connection) -> Result<ScoreScale, Box<dyn Error>> {
+    let scale = conn.query_row(
+        "SELECT IFNULL(MAX(frequency), 0),
+                    (SELECT COUNT(*) FROM documents),
+                    (SELECT IFNULL(MAX(rank), 0) FROM external_frequencies),
+                    IFNULL(MAX(ABS(priority)), 0)
+                FROM expressions;",
+        params![],
+        |row| {
+            Ok(ScoreScale {
+                max_frequency: row.get(0)?,
+                document_count: row.get(1)?,
+                max_rank: row.get(2)?,
+                max_priority: row.get(3)?,
+            })
+        },
+    )?;
+
+    Ok(scale)
+}
+
+/// Mark the expressions in anki whose review interval reaches learned_interval as learned,
+/// and mark the expressions that a sync marked as learned as not learned once their interval falls below it.
+///
+/// Expressions marked with `learn` stay learned whatever their interval.
+///
+/// Returns the number of expressions that became learned and the number that became not learned.
+pub fn update_is_learned_for_interval_map(
+    conn: &mut Connection,
+    interval_map: &HashMap<String, i64>,
+    learned_interval: i64,
+) -> Result<(usize, usize), Box<dyn Error>> {
+    let tx = conn.transaction()?;
+
+    let mut learned_count = 0;
+    let mut unlearned_count = 0;
+    for (expression, interval) in interval_map.iter() {
+        let is_learned = *interval >= learned_interval;
+
+        match query::expression::select_is_learned(&tx, expression)? {
+            Some((false, _)) if is_learned => {
+                query::expression::update_is_learned_by_sync(&tx, expression, true)?;
+                learned_count += 1;
+            }
+            // a lapse only unlearns what a sync learned
+            Some((true, true)) if !is_learned => {
+                query::expression::update_is_learned_by_sync(&tx, expression, false)?;
+                unlearned_count += 1;
+            }
+            _ => {}
+        }
+    }
+
+    tx.commit()?;
+
+    Ok((learned_count, unlearned_count))
+}
+
+const SEARCH_SENTENCE_FTS: &str = "SELECT sentence FROM sentences JOIN sentences_fts ON sentences_fts.rowid = sentences.id WHERE sentences_fts MATCH ? ORDER BY sentences.id ASC LIMIT ?;";
+
+const SEARCH_SENTENCE_SUBSTRING: &str =
+    "SELECT sentence FROM sentences WHERE instr(sentence, ?) > 0 ORDER BY id ASC LIMIT ?;";
+
+/// One row for each surface string of the expression in each of the first sentences containing it.
+const SEARCH_SENTENCE_LEMMA: &str = "SELECT matched.sentence_id, sentence, surface_string FROM (SELECT DISTINCT sentence_id FROM occurrences JOIN expressions ON expressions.id = expression_id WHERE expression = ?1 ORDER BY sentence_id ASC LIMIT ?2) AS matched JOIN sentences ON sentences.id = matched.sentence_id JOIN occurrences ON occurrences.sentence_id = matched.sentence_id JOIN expressions ON expressions.id = occurrences.expression_id JOIN surface_strings ON surface_strings.id = surface_string_id WHERE expression = ?1 GROUP BY matched.sentence_id, surface_string_id ORDER BY matched.sentence_id ASC, MIN(position) ASC;";
+
+/// Quote a search string as an FTS5 phrase, whitespace separates the words of the phrase.
+fn fts_phrase(string: &str) -> String {
+    format!("\"{}\"", string.replace('"', "\"\""))
+}
+
+/// Search the full-text index for sentences containing the words in phrase.
+pub fn search_sentence_list(
+    conn: &Connection,
+    phrase: &str,
+    limit: i32,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut statement = conn.prepare(SEARCH_SENTENCE_FTS)?;
+
+    let sentence_list = statement
+        .query_map(params![fts_phrase(phrase), limit], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(sentence_list)
+}
+
+/// Search for sentences containing substring anywhere in the raw text.
+pub fn search_sentence_list_for_substring(
+    conn: &Connection,
+    substring: &str,
+    limit: i32,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut statement = conn.prepare(SEARCH_SENTENCE_SUBSTRING)?;
+
+    let sentence_list = statement
+        .query_map(params![substring, limit], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(sentence_list)
+}
+
+/// An unknown expression with the sentences in which it is the only unknown expression.
+#[derive(Debug, PartialEq)]
+pub struct MinedExpression {
+    pub expression: String,
+    pub frequency: i64,
+    pub sentence_list: Vec<String>,
+}
+
+/// Find the i+1 sentences, the sentences with exactly one unknown expression that is not excluded.
+///
+/// The sentences are grouped by their unknown expression, most frequent expression first,
+/// and the shortest sentences of each expression come first.
+///
+/// # Arguments
+///
+/// * `conn` - A &Connection object
+/// * `limit` - The maximum number of sentences for each expression, -1 for all
+pub fn select_mined_expression_list(
+    conn: &Connection,
+    limit: i32,
+) -> Result<Vec<MinedExpression>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT e.expression, e.frequency, s.sentence
+                FROM (
+                    SELECT o.sentence_id, MIN(o.expression_id) AS expression_id
+                    FROM occurrences AS o JOIN expressions AS e ON e.id = o.expression_id
+                    WHERE {}
+                    GROUP BY o.sentence_id
+                    HAVING COUNT(DISTINCT o.expression_id) = 1
+                ) AS u
+                JOIN expressions AS e ON e.id = u.expression_id
+                JOIN sentences AS s ON s.id = u.sentence_id
+                ORDER BY e.frequency DESC, e.expression ASC, LENGTH(s.sentence) ASC, s.id ASC;",
+        stats::IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let row_list = statement.query_map(params![], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut expression_list: Vec<MinedExpression> = Vec::new();
+    for row in row_list {
+        let (expression, frequency, sentence) = row?;
+
+        if expression_list
+            .last()
+            .is_none_or(|last| last.expression != expression)
+        {
+            expression_list.push(MinedExpression {
+                expression,
+                frequency,
+                sentence_list: Vec::new(),
+            });
+        }
+
+        if let Some(last) = expression_list.last_mut() {
+            if limit < 0 || last.sentence_list.len() < limit as usize {
+                last.sentence_list.push(sentence);
+            }
+        }
+    }
+
+    Ok(expression_list)
+}
+
+/// Sentences paired with the surface strings that matched in them.
+pub type SentenceMatchList = Vec<(String, Vec<String>)>;
+
+/// Search for sentences containing the expression in any surface form.
+///
+/// Returns each sentence with the surface strings of the expression that occur in it.
+pub fn search_sentence_list_for_expression(
+    conn: &Connection,
+    expression: &str,
+    limit: i32,
+) -> Result<SentenceMatchList, Box<dyn Error>> {
+    let mut statement = conn.prepare(SEARCH_SENTENCE_LEMMA)?;
+
+    let row_list = statement
+        .query_map(params![expression, limit], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the rows of a sentence are next to each other
+    let mut sentence_list: SentenceMatchList = Vec::new();
+    let mut last_id = None;
+    for (id, sentence, surface_string) in row_list {
+        match sentence_list.last_mut() {
+            Some((_, surface_string_list)) if last_id == Some(id) => {
+                surface_string_list.push(surface_string)
+            }
+            _ => sentence_list.push((sentence, vec![surface_string])),
+        }
+
+        last_id = Some(id);
+    }
+
+    Ok(sentence_list)
+}
+
+/// Select the largest expression id, expressions inserted afterwards have a larger id.
+pub fn select_max_expression_id(conn: &Connection) -> Result<i64, Box<dyn Error>> {
+    let id: i64 = conn.query_row(
+        "SELECT IFNULL(MAX(id), 0) FROM expressions;",
+        params![],
+        |row| row.get(0),
+    )?;
+
+    Ok(id)
+}
+
+/// Select the expressions with an id greater than min_id that exclusion rules can apply to.
+///
+/// Expressions that are already excluded, learned or in Anki are skipped.
+pub fn select_exclusion_candidate_list(
+    conn: &Connection,
+    min_id: i64,
+) -> Result<Vec<exclusion::Candidate>, Box<dyn Error>> {
+    let mut statement = conn.prepare(SELECT_EXCLUSION_CANDIDATE)?;
+
+    let candidate_list = statement
+        .query_map(params![min_id], |row| {
+            let pos_list: Option<String> = row.get(1)?;
+
+            Ok(exclusion::Candidate {
+                expression: row.get(0)?,
+                pos_list: pos_list
+                    .map(|x| x.split(',').map(|x| x.to_string()).collect())
+                    .unwrap_or_default(),
+                frequency: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<exclusion::Candidate>, _>>()?;
+
+    Ok(candidate_list)
+}
+
+const SELECT_EXCLUSION_CANDIDATE: &str = "SELECT e.expression, group_concat(DISTINCT p.pos), e.frequency FROM expressions AS e LEFT JOIN occurrences AS o ON o.expression_id = e.id LEFT JOIN pos AS p ON p.id = o.pos_id WHERE IFNULL(e.is_excluded, 0) = 0 AND e.is_learned = 0 AND e.in_anki = 0 AND e.id > ? GROUP BY e.id ORDER BY e.id ASC;";
+
+const SELECT_SURFACE_STRING_FOR_EXPRESSION: &str = "SELECT DISTINCT surface_string FROM surface_strings JOIN occurrences ON surface_string_id = surface_strings.id JOIN expressions ON expressions.id = expression_id WHERE expression = ? ORDER BY surface_strings.id ASC;";
+
+const SELECT_EXAMPLE_SENTENCE_FOR_EXPRESSION: &str = "SELECT DISTINCT sentence FROM sentences JOIN occurrences ON sentence_id = sentences.id JOIN expressions ON expressions.id = expression_id WHERE expression = ? ORDER BY sentences.id ASC LIMIT ?;";
+
+pub fn select_surface_string_for_expression(
+    conn: &Connection,
+    expression: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut statement = conn.prepare_cached(SELECT_SURFACE_STRING_FOR_EXPRESSION)?;
+
+    let surface_string_list = statement
+        .query_map(params![expression], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(surface_string_list)
+}
+
+/// Get up to limit sentences for an expression, in the order they were imported.
+pub fn select_example_sentence_for_expression(
+    conn: &Connection,
+    expression: &str,
+    limit: i32,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut statement = conn.prepare_cached(SELECT_EXAMPLE_SENTENCE_FOR_EXPRESSION)?;
+
+    let sentence_list = statement
+        .query_map(params![expression, limit], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(sentence_list)
+}
+
 const SELECT_POS_FOR_EXPRESSION: &str = "SELECT pos FROM pos JOIN expressions_pos_sentences_surface_strings ON pos_id = pos.id JOIN expressions ON expressions.id = expression_id WHERE expression = ?;";
 
 const SELECT_SENTENCE_FOR_EXPRESSION: &str = "SELECT sentence FROM sentences JOIN expressions_pos_sentences_surface_strings ON sentence_id = sentences.id JOIN expressions ON expressions.id = expression_id WHERE expression = ?;";
@@ -275,8 +1025,7 @@ pub fn select_pos_for_expression(
 
     let pos_list: Vec<String> = statement
         .query_map(params, |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     Ok(pos_list)
 }
@@ -290,8 +1039,7 @@ pub fn select_sentence_for_expression(
 
     let sentence_list: Vec<String> = statement
         .query_map(params, |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     Ok(sentence_list)
 }
@@ -321,15 +1069,14 @@ pub fn select_pos_list(
 
     let pos_list: Vec<String> = statement
         .query_map(params![], |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     Ok(pos_list)
 }
 
 pub fn update_is_excluded_for_pos_list(
     conn: &mut Connection,
-    pos_list: &Vec<String>,
+    pos_list: &[String],
     is_excluded: bool,
     callback: &dyn Fn(),
 ) -> Result<(), Box<dyn Error>> {
@@ -361,8 +1108,7 @@ fn select_expression_list_for_pos(
 
     let expression_list: Vec<String> = statement
         .query_map(params![pos], |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     Ok(expression_list)
 }
@@ -386,3 +1132,676 @@ sql!(
     UPDATE_IS_EXCLUDED_FOR_EXPRESSION,
     params = [conn: &Connection, expression: &str, is_excluded: i32]
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// open an in-memory database with the latest schema
+    fn connect_in_memory() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("Failed to open database");
+        migration::migrate(&mut conn, None).expect("Failed to migrate database");
+
+        conn
+    }
+
+    fn expression(
+        expression: &str,
+        pos: &str,
+        sentence: &str,
+        surface_string: &str,
+        position: usize,
+    ) -> Expression {
+        Expression::new(expression.to_string())
+            .pos(vec![pos.to_string()])
+            .sentence(vec![sentence.to_string()])
+            .surface_string(vec![surface_string.to_string()])
+            .position(position)
+    }
+
+    fn select_frequency(conn: &Connection, expression: &str) -> i64 {
+        conn.query_row(
+            "SELECT frequency FROM expressions WHERE expression = ?;",
+            params![expression],
+            |row| row.get(0),
+        )
+        .expect("Failed to select frequency")
+    }
+
+    #[test]
+    fn test_insert_expression_list() {
+        let mut conn = connect_in_memory();
+
+        let expression_list = vec![
+            expression("名前", "名詞", "名前は何ですか", "名前", 0),
+            expression("何", "名詞", "名前は何ですか", "何", 2),
+            expression("名前", "名詞", "名前を書く", "名前", 0),
+            expression("書く", "動詞", "名前を書く", "書く", 2),
+        ];
+
+        insert_expression_list(&mut conn, expression_list, &|| {})
+            .expect("Failed to insert expression list");
+
+        assert_eq!(select_frequency(&conn, "名前"), 2);
+        assert_eq!(select_frequency(&conn, "書く"), 1);
+
+        let sentence_list = vec!["名前を書く".to_string(), "新しい文".to_string()];
+        let sentence_set = select_imported_sentence_list(&conn, &sentence_list)
+            .expect("Failed to select imported sentences");
+
+        let expression_list = filter_imported_expression_list(
+            &sentence_set,
+            vec![
+                expression("名前", "名詞", "名前を書く", "名前", 0),
+                expression("文", "名詞", "新しい文", "文", 1),
+            ],
+        );
+
+        assert_eq!(expression_list.len(), 1);
+        assert_eq!(expression_list[0].get_expression(), "文");
+    }
+
+    /// assert that a word repeated in one sentence is counted per occurrence,
+    /// and that importing the same sentence again does not change the count
+    #[test]
+    fn test_insert_expression_list_idempotent() {
+        let mut conn = connect_in_memory();
+
+        let expression_list = || {
+            vec![
+                expression("猫", "名詞", "猫と猫", "猫", 0),
+                expression("と", "助詞", "猫と猫", "と", 1),
+                expression("猫", "名詞", "猫と猫", "猫", 2),
+            ]
+        };
+
+        insert_expression_list(&mut conn, expression_list(), &|| {})
+            .expect("Failed to insert expression list");
+        assert_eq!(select_frequency(&conn, "猫"), 2);
+
+        insert_expression_list(&mut conn, expression_list(), &|| {})
+            .expect("Failed to insert expression list");
+        assert_eq!(select_frequency(&conn, "猫"), 2);
+    }
+
+    #[test]
+    fn test_select_expression_list_with_query() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫が走る", "猫", 0),
+                expression("が", "助詞", "猫が走る", "が", 1),
+                expression("走る", "動詞", "猫が走る", "走る", 2),
+                expression("猫", "名詞", "猫だ", "猫", 0),
+                expression("犬", "名詞", "犬", "犬", 0),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+        insert_document(
+            &mut conn,
+            "Book 3",
+            "/books/3.txt",
+            &["猫が走る".to_string()],
+            86400,
+        )
+        .expect("Failed to insert document");
+
+        let select = |query: &str| -> Vec<String> {
+            let filter = Filter::default().parse(query).expect("Failed to parse");
+            select_expression_record_list(&conn, &filter, "id", true, -1)
+                .expect("Failed to select expression list")
+                .into_iter()
+                .map(|x| x.expression)
+                .collect()
+        };
+
+        assert_eq!(select("pos:名詞"), vec!["猫", "犬"]);
+        assert_eq!(select(r#"source:"Book 3" -pos:助詞"#), vec!["猫", "走る"]);
+        assert_eq!(select("freq>=2"), vec!["猫"]);
+        assert_eq!(select("status:learned"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_insert_document_seen() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫が走る", "猫", 0),
+                expression("走る", "動詞", "猫が走る", "走る", 2),
+                expression("猫", "名詞", "猫だ", "猫", 0),
+                expression("犬", "名詞", "犬", "犬", 0),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+        insert_document(
+            &mut conn,
+            "a.txt",
+            "/a.txt",
+            &["猫が走る".to_string()],
+            10 * 86400,
+        )
+        .expect("Failed to insert document");
+        insert_document(
+            &mut conn,
+            "b.txt",
+            "/b.txt",
+            &["猫だ".to_string()],
+            5 * 86400,
+        )
+        .expect("Failed to insert document");
+
+        let seen: (Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT first_seen, last_seen FROM expressions WHERE expression = '猫';",
+                params![],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(seen, (Some(5 * 86400), Some(10 * 86400)));
+
+        let select = |filter: &Filter, order_by: &str| -> Vec<String> {
+            select_expression_record_list(&conn, filter, order_by, false, -1)
+                .expect("Failed to select expression list")
+                .into_iter()
+                .map(|x| x.expression)
+                .collect()
+        };
+
+        // expressions that were never seen come last
+        assert_eq!(select(&Filter::default(), "recent").last().unwrap(), "犬");
+        assert_eq!(
+            select(&Filter::default().since(Some(10 * 86400)), "expression"),
+            vec!["走る", "猫"]
+        );
+    }
+
+    #[test]
+    fn test_select_expression_record_list_dispersion() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫と太郎", "猫", 0),
+                expression("太郎", "名詞", "猫と太郎", "太郎", 2),
+                expression("猫", "名詞", "猫と太郎と太郎", "猫", 0),
+                expression("太郎", "名詞", "猫と太郎と太郎", "太郎", 2),
+                expression("太郎", "名詞", "猫と太郎と太郎", "太郎", 4),
+                expression("猫", "名詞", "猫だ", "猫", 0),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+        insert_document(
+            &mut conn,
+            "a.txt",
+            "/a.txt",
+            &["猫と太郎と太郎".to_string()],
+            0,
+        )
+        .expect("Failed to insert document");
+        insert_document(&mut conn, "b.txt", "/b.txt", &["猫だ".to_string()], 0)
+            .expect("Failed to insert document");
+
+        let record_list =
+            select_expression_record_list(&conn, &Filter::default(), "dispersion", false, -1)
+                .expect("Failed to select expression records");
+
+        // 猫 is a third of a.txt and all of b.txt, 太郎 only occurs in a.txt
+        let neko = &record_list[0];
+        assert_eq!(neko.expression, "猫");
+        assert_eq!(neko.document_count, 2);
+        assert!((neko.per_million - 500000.0).abs() < 1e-6);
+        assert!((neko.dispersion.unwrap() - 0.5).abs() < 1e-9);
+
+        let taro = &record_list[1];
+        assert_eq!(taro.expression, "太郎");
+        assert!((taro.dispersion.unwrap() - 0.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_insert_frequency_list() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫だ", "猫", 0),
+                expression("猫", "名詞", "猫と犬", "猫", 0),
+                expression("犬", "名詞", "猫と犬", "犬", 2),
+                expression("鳥", "名詞", "鳥", "鳥", 0),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        let entry = |expression: &str, reading: &str, rank: i64| FrequencyEntry {
+            expression: expression.to_string(),
+            reading: reading.to_string(),
+            rank,
+        };
+
+        let count = insert_frequency_list(
+            &mut conn,
+            "list",
+            &[
+                entry("犬", "いぬ", 10),
+                entry("猫", "ねこ", 500),
+                entry("猫", "ねこ", 200),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert frequency list");
+        assert_eq!(count, 2);
+        assert_eq!(
+            select_frequency_list_list(&conn).expect("Failed to select frequency lists"),
+            vec![("list".to_string(), 2)]
+        );
+
+        let select = |conn: &Connection, order_by: &str| -> Vec<(String, Option<i64>)> {
+            select_expression_record_list(conn, &Filter::default(), order_by, false, -1)
+                .expect("Failed to select expression records")
+                .into_iter()
+                .map(|x| (x.expression, x.rank))
+                .collect()
+        };
+
+        assert_eq!(
+            select(&conn, "rank"),
+            vec![
+                ("犬".to_string(), Some(10)),
+                ("猫".to_string(), Some(200)),
+                ("鳥".to_string(), None)
+            ]
+        );
+        assert_eq!(select(&conn, "combined")[0].0, "犬");
+
+        assert!(delete_frequency_list(&mut conn, "list").expect("Failed to delete list"));
+        assert_eq!(select(&conn, "rank")[0].1, None);
+    }
+
+    #[test]
+    fn test_select_mined_expression_list() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫と犬", "猫", 0),
+                expression("と", "助詞", "猫と犬", "と", 1),
+                expression("犬", "名詞", "猫と犬", "犬", 2),
+                expression("猫", "名詞", "猫だ", "猫", 0),
+                expression("だ", "助動詞", "猫だ", "だ", 1),
+                expression("犬", "名詞", "犬と猫だ", "犬", 0),
+                expression("と", "助詞", "犬と猫だ", "と", 1),
+                expression("猫", "名詞", "犬と猫だ", "猫", 2),
+                expression("だ", "助動詞", "犬と猫だ", "だ", 3),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+        conn.execute(
+            "UPDATE expressions SET is_learned = 1 WHERE expression IN ('と', 'だ');",
+            params![],
+        )
+        .expect("Failed to update expressions");
+        conn.execute(
+            "UPDATE expressions SET is_excluded = 1 WHERE expression = '犬';",
+            params![],
+        )
+        .expect("Failed to update expressions");
+
+        let expression_list =
+            select_mined_expression_list(&conn, 2).expect("Failed to mine sentences");
+
+        assert_eq!(
+            expression_list,
+            vec![MinedExpression {
+                expression: "猫".to_string(),
+                frequency: 3,
+                sentence_list: vec!["猫だ".to_string(), "猫と犬".to_string()],
+            }]
+        );
+
+        let expression_list =
+            select_mined_expression_list(&conn, 0).expect("Failed to mine sentences");
+        assert!(expression_list[0].sentence_list.is_empty());
+    }
+
+    #[test]
+    fn test_update_tag_for_expression_list() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫と犬", "猫", 0),
+                expression("犬", "名詞", "猫と犬", "犬", 2),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        let count = update_tag_for_expression_list(
+            &mut conn,
+            "動物",
+            &[
+                Expression::new("猫".to_string()),
+                Expression::new("犬".to_string()),
+                Expression::new("鳥".to_string()),
+            ],
+            true,
+            &|| {},
+        )
+        .expect("Failed to add tag");
+        assert_eq!(count, 1);
+        assert_eq!(
+            select_tag_list(&conn).expect("Failed to select tags"),
+            vec![("動物".to_string(), 2)]
+        );
+
+        let filter = Filter::new(false, false, false).tag_list(vec!["動物".to_string()]);
+        update_tag_for_expression_list(
+            &mut conn,
+            "動物",
+            &[Expression::new("犬".to_string())],
+            false,
+            &|| {},
+        )
+        .expect("Failed to remove tag");
+
+        let record_list = select_expression_record_list(&conn, &filter, "id", true, -1)
+            .expect("Failed to select expression list");
+        assert_eq!(record_list.len(), 1);
+        assert_eq!(record_list[0].expression, "猫");
+        assert_eq!(
+            select_tag_for_expression(&conn, "猫").expect("Failed to select tags"),
+            vec!["動物".to_string()]
+        );
+
+        update_tag_for_expression_list(
+            &mut conn,
+            "動物",
+            &[Expression::new("猫".to_string())],
+            false,
+            &|| {},
+        )
+        .expect("Failed to remove tag");
+        assert!(select_tag_list(&conn)
+            .expect("Failed to select tags")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_backup_restore() {
+        let mut conn = connect_in_memory();
+        let path =
+            std::env::temp_dir().join(format!("vocabulist_backup_{}.db", std::process::id()));
+
+        insert_expression_list(
+            &mut conn,
+            vec![expression("猫", "名詞", "猫だ", "猫", 0)],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        backup(&conn, &path, &|_, _| {}).expect("Failed to back up database");
+
+        insert_expression_list(
+            &mut conn,
+            vec![expression("猫", "名詞", "猫と猫", "猫", 0)],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+        assert_eq!(select_frequency(&conn, "猫"), 2);
+
+        let database_path =
+            std::env::temp_dir().join(format!("vocabulist_restore_{}.db", std::process::id()));
+        restore(&mut conn, &database_path, &path, &|_, _| {}).expect("Failed to restore database");
+        assert_eq!(select_frequency(&conn, "猫"), 1);
+
+        // the database before the restore is kept
+        let copy = Connection::open(restore_backup_path(&database_path)).unwrap();
+        assert_eq!(select_frequency(&copy, "猫"), 2);
+
+        std::fs::remove_file(&path).expect("Failed to remove backup");
+        std::fs::remove_file(restore_backup_path(&database_path)).expect("Failed to remove copy");
+    }
+
+    #[test]
+    fn test_recount() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![expression("猫", "名詞", "猫", "猫", 0)],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        conn.execute("UPDATE expressions SET frequency = 5;", params![])
+            .unwrap();
+
+        assert_eq!(recount(&mut conn).expect("Failed to recount"), 1);
+        assert_eq!(select_frequency(&conn, "猫"), 1);
+        assert_eq!(recount(&mut conn).expect("Failed to recount"), 0);
+    }
+
+    #[test]
+    fn test_update_is_learned_for_expression_list() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![expression("猫", "名詞", "猫", "猫", 0)],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        let count = update_is_learned_for_expression_list(
+            &mut conn,
+            &[
+                Expression::new("猫".to_string()),
+                Expression::new("犬".to_string()),
+            ],
+            true,
+            &|| {},
+        )
+        .expect("Failed to update is_learned");
+        assert_eq!(count, 1);
+
+        let learned_list =
+            select_expression_record_list(&conn, &Filter::new(true, true, true), "id", true, -1)
+                .expect("Failed to select expression list");
+        let unlearned_list =
+            select_expression_record_list(&conn, &Filter::new(true, true, false), "id", true, -1)
+                .expect("Failed to select expression list");
+
+        assert_eq!(learned_list.len(), 2);
+        assert_eq!(unlearned_list.len(), 0);
+
+        // the word is known before it is imported
+        insert_expression_list(
+            &mut conn,
+            vec![expression("犬", "名詞", "犬", "犬", 0)],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        assert_eq!(select_frequency(&conn, "犬"), 1);
+        let unlearned_list =
+            select_expression_record_list(&conn, &Filter::new(true, true, false), "id", true, -1)
+                .expect("Failed to select expression list");
+        assert_eq!(unlearned_list.len(), 0);
+    }
+
+    #[test]
+    fn test_update_is_learned_for_interval_map() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫と犬", "猫", 0),
+                expression("犬", "名詞", "猫と犬", "犬", 2),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        let mut interval_map: HashMap<String, i64> = HashMap::new();
+        interval_map.insert("猫".to_string(), 30);
+        interval_map.insert("犬".to_string(), 3);
+
+        let select_is_learned = |conn: &Connection, expression: &str| -> bool {
+            conn.query_row(
+                "SELECT is_learned FROM expressions WHERE expression = ?;",
+                params![expression],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        let count = update_is_learned_for_interval_map(&mut conn, &interval_map, 21)
+            .expect("Failed to update is_learned");
+        assert_eq!(count, (1, 0));
+
+        // a lapse unlearns an expression that a sync learned, but not one marked with learn
+        update_is_learned_for_expression_list(
+            &mut conn,
+            &[crate::Expression::new("犬".to_string())],
+            true,
+            &|| {},
+        )
+        .expect("Failed to update is_learned");
+        interval_map.insert("猫".to_string(), 1);
+
+        let count = update_is_learned_for_interval_map(&mut conn, &interval_map, 21)
+            .expect("Failed to update is_learned");
+        assert_eq!(count, (0, 1));
+        assert!(!select_is_learned(&conn, "猫"));
+        assert!(select_is_learned(&conn, "犬"));
+    }
+
+    #[test]
+    fn test_search_sentence_list() {
+        let mut conn = connect_in_memory();
+
+        insert_expression_list(
+            &mut conn,
+            vec![
+                expression("東京", "名詞", "東京に行った", "東京", 0),
+                expression("に", "助詞", "東京に行った", "に", 1),
+                expression("行く", "動詞", "東京に行った", "行っ", 2),
+                expression("た", "助動詞", "東京に行った", "た", 3),
+                expression("行く", "動詞", "京都へ行く", "行く", 2),
+                expression("千", "名詞", "1,000円と千円", "1,000", 0),
+                expression("千", "名詞", "1,000円と千円", "千", 3),
+            ],
+            &|| {},
+        )
+        .expect("Failed to insert expression list");
+
+        let sentence_list =
+            search_sentence_list(&conn, "東京 に", -1).expect("Failed to search sentences");
+        assert_eq!(sentence_list, vec!["東京に行った"]);
+
+        // the index holds whole words, so part of a word does not match
+        let sentence_list =
+            search_sentence_list(&conn, "京", -1).expect("Failed to search sentences");
+        assert!(sentence_list.is_empty());
+
+        let sentence_list = search_sentence_list_for_substring(&conn, "京", -1)
+            .expect("Failed to search sentences");
+        assert_eq!(sentence_list, vec!["東京に行った", "京都へ行く"]);
+
+        let sentence_list = search_sentence_list_for_expression(&conn, "行く", -1)
+            .expect("Failed to search sentences");
+        assert_eq!(
+            sentence_list,
+            vec![
+                ("東京に行った".to_string(), vec!["行っ".to_string()]),
+                ("京都へ行く".to_string(), vec!["行く".to_string()]),
+            ]
+        );
+
+        // a surface string may contain a comma
+        let sentence_list = search_sentence_list_for_expression(&conn, "千", 1)
+            .expect("Failed to search sentences");
+        assert_eq!(
+            sentence_list,
+            vec![(
+                "1,000円と千円".to_string(),
+                vec!["1,000".to_string(), "千".to_string()]
+            )]
+        );
+    }
+
+    /// benchmark the import path on a corpus of one million tokens
+    ///     run with `cargo test --release -- --ignored bench_insert_expression_list --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_insert_expression_list() {
+        const SENTENCE_COUNT: u64 = 50_000;
+        const TOKEN_COUNT: u64 = 20;
+        const VOCABULARY_SIZE: u64 = 30_000;
+
+        // a linear congruential generator skewed towards small ids, so the corpus is roughly zipfian
+        let mut seed: u64 = 42;
+        let mut next_id = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let x = (seed >> 33) % VOCABULARY_SIZE;
+            x * x / VOCABULARY_SIZE
+        };
+
+        let mut expression_list: Vec<Expression> = Vec::new();
+        for sentence in 0..SENTENCE_COUNT {
+            let sentence_string = format!("文{}", sentence);
+            for position in 0..TOKEN_COUNT {
+                let id = next_id();
+                expression_list.push(expression(
+                    &format!("語{}", id),
+                    &format!("品詞{}", id % 10),
+                    &sentence_string,
+                    &format!("語{}", id),
+                    position as usize,
+                ));
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("vocabulist_bench_{}.db", std::process::id()));
+        let mut conn = connect(&path).expect("Failed to open database");
+
+        let start = Instant::now();
+        insert_expression_list(&mut conn, expression_list, &|| {})
+            .expect("Failed to insert expression list");
+        println!(
+            "Inserted {} tokens in {:?}",
+            SENTENCE_COUNT * TOKEN_COUNT,
+            start.elapsed()
+        );
+
+        let sentence_list: Vec<String> = (0..SENTENCE_COUNT).map(|x| format!("文{}", x)).collect();
+
+        let start = Instant::now();
+        let sentence_set = select_imported_sentence_list(&conn, &sentence_list)
+            .expect("Failed to select imported sentences");
+        println!(
+            "Found {} imported sentences in {:?}",
+            sentence_set.len(),
+            start.elapsed()
+        );
+
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::error::Error;
+
+/* Document Functions */
+
+pub fn insert(tx: &Transaction, name: &str, path: &str) -> Result<i64, Box<dyn Error>> {
+    let params = params![name, path];
+    let query = "INSERT INTO documents (name, path) VALUES (?, ?);";
+
+    tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(tx.last_insert_rowid())
+}
+
+pub fn select_id(tx: &Transaction, path: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    let params = params![path];
+    let query = "SELECT id FROM documents WHERE path = ?;";
+
+    let id: Option<i64> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| row.get(0))
+        .optional()?;
+
+    Ok(id)
+}
+
+/// Set the import time of the document to now.
+pub fn update_imported_at(tx: &Transaction, id: i64) -> Result<(), Box<dyn Error>> {
+    let query = "UPDATE documents SET imported_at = strftime('%s', 'now') WHERE id = ?;";
+
+    tx.prepare_cached(query)?.execute(params![id])?;
+
+    Ok(())
+}
+
+/// Remove every sentence from the document.
+pub fn delete_sentence_list(tx: &Transaction, id: i64) -> Result<(), Box<dyn Error>> {
+    let query = "DELETE FROM document_sentences WHERE document_id = ?;";
+
+    tx.prepare_cached(query)?.execute(params![id])?;
+
+    Ok(())
+}
+
+/// Add a sentence to the document, a sentence that is already in the document keeps its first position.
+pub fn insert_sentence(
+    tx: &Transaction,
+    id: i64,
+    sentence_id: i64,
+    position: usize,
+) -> Result<(), Box<dyn Error>> {
+    let query = "INSERT OR IGNORE INTO document_sentences (document_id, sentence_id, position) VALUES (?, ?, ?);";
+
+    tx.prepare_cached(query)?
+        .execute(params![id, sentence_id, position as i64])?;
+
+    Ok(())
+}
+
+/// Get the ids of the documents with the name or path.
+pub fn select_id_list_for_name(conn: &Connection, name: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+    let query = "SELECT id FROM documents WHERE name = ?1 OR path = ?1 ORDER BY id ASC;";
+
+    let mut statement = conn.prepare_cached(query)?;
+    let id_list = statement
+        .query_map(params![name], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+
+    Ok(id_list)
+}
@@ -1,17 +1,72 @@
-use rusqlite::{params, Transaction};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
 use std::error::Error;
 
 /* Expression Functions */
 
-pub fn insert(tx: &Transaction, string: &str) -> Result<(), Box<dyn Error>> {
+pub fn insert(tx: &Transaction, string: &str) -> Result<i64, Box<dyn Error>> {
     let params = params![string];
-    let query = "INSERT OR IGNORE INTO expressions (expression) VALUES (?) ON CONFLICT (expression) DO UPDATE SET frequency = frequency + 1;";
+    let query = "INSERT INTO expressions (expression, frequency) VALUES (?, 0);";
 
-    tx.execute(query, params)?;
+    tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(tx.last_insert_rowid())
+}
+
+pub fn update_frequency(tx: &Transaction, id: i64, count: i64) -> Result<(), Box<dyn Error>> {
+    let params = params![count, id];
+    let query = "UPDATE expressions SET frequency = frequency + ? WHERE id = ?;";
+
+    tx.prepare_cached(query)?.execute(params)?;
 
     Ok(())
 }
 
+/// Set the frequency of every expression to its number of occurrences
+/// and return the number of expressions whose frequency changed.
+pub fn update_frequency_from_occurrences(conn: &Connection) -> Result<usize, Box<dyn Error>> {
+    let query = "UPDATE expressions SET frequency = (SELECT COUNT(*) FROM occurrences WHERE expression_id = expressions.id) WHERE frequency IS NOT (SELECT COUNT(*) FROM occurrences WHERE expression_id = expressions.id);";
+
+    let count = conn.execute(query, params![])?;
+
+    Ok(count)
+}
+
+/// Set the first and last seen times of every expression from the import times of the documents it occurs in.
+pub fn update_seen_from_documents(conn: &Connection) -> Result<usize, Box<dyn Error>> {
+    let query = "UPDATE expressions SET (first_seen, last_seen) = (
+                    SELECT MIN(imported_at), MAX(imported_at)
+                    FROM occurrences
+                    JOIN document_sentences ON document_sentences.sentence_id = occurrences.sentence_id
+                    JOIN documents ON documents.id = document_id
+                    WHERE expression_id = expressions.id
+                );";
+
+    let count = conn.execute(query, params![])?;
+
+    Ok(count)
+}
+
+/// Extend the first and last seen times of the expressions in the document to include seen_at.
+pub fn update_seen_for_document(
+    tx: &Transaction,
+    document_id: i64,
+    seen_at: i64,
+) -> Result<usize, Box<dyn Error>> {
+    let query = "UPDATE expressions
+                SET first_seen = MIN(IFNULL(first_seen, ?1), ?1), last_seen = MAX(IFNULL(last_seen, ?1), ?1)
+                WHERE id IN (
+                    SELECT expression_id FROM occurrences
+                    JOIN document_sentences ON document_sentences.sentence_id = occurrences.sentence_id
+                    WHERE document_id = ?2
+                );";
+
+    let count = tx
+        .prepare_cached(query)?
+        .execute(params![seen_at, document_id])?;
+
+    Ok(count)
+}
+
 pub fn update_is_excluded(
     tx: &Transaction,
     string: &str,
@@ -26,11 +81,76 @@ pub fn update_is_excluded(
     Ok(())
 }
 
-pub fn select_id(tx: &Transaction, string: &str) -> Result<i32, Box<dyn Error>> {
+pub fn update_is_learned(
+    tx: &Transaction,
+    string: &str,
+    is_learned: bool,
+) -> Result<(), Box<dyn Error>> {
+    let is_learned = if is_learned { 1 } else { 0 };
+    let params = params![is_learned, string];
+    let query =
+        "UPDATE expressions SET is_learned = ?, is_learned_by_sync = 0 WHERE expression = ?;";
+
+    tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(())
+}
+
+/// Set is_learned from the review interval of a card, and remember that a sync set it.
+pub fn update_is_learned_by_sync(
+    tx: &Transaction,
+    string: &str,
+    is_learned: bool,
+) -> Result<(), Box<dyn Error>> {
+    let is_learned = if is_learned { 1 } else { 0 };
+    let params = params![is_learned, string];
+    let query =
+        "UPDATE expressions SET is_learned = ?1, is_learned_by_sync = ?1 WHERE expression = ?2;";
+
+    tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(())
+}
+
+/// Set the manual priority of an expression, returns false if the expression does not exist.
+pub fn update_priority(
+    tx: &Transaction,
+    string: &str,
+    priority: i64,
+) -> Result<bool, Box<dyn Error>> {
+    let query = "UPDATE expressions SET priority = ? WHERE expression = ?;";
+
+    let count = tx
+        .prepare_cached(query)?
+        .execute(params![priority, string])?;
+
+    Ok(count > 0)
+}
+
+/// Select is_learned and whether a sync set it.
+pub fn select_is_learned(
+    tx: &Transaction,
+    string: &str,
+) -> Result<Option<(bool, bool)>, Box<dyn Error>> {
+    let params = params![string];
+    let query = "SELECT is_learned, is_learned_by_sync FROM expressions WHERE expression = ?;";
+
+    let is_learned: Option<(bool, bool)> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+
+    Ok(is_learned)
+}
+
+pub fn select_id(tx: &Transaction, string: &str) -> Result<Option<i64>, Box<dyn Error>> {
     let params = params![string];
     let query = "SELECT id FROM expressions WHERE expression = ?;";
 
-    let id: i32 = tx.query_row(query, params, |row| row.get(0))?;
+    let id: Option<i64> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| row.get(0))
+        .optional()?;
 
     Ok(id)
 }
@@ -0,0 +1,76 @@
+use rusqlite::{params, Connection, Transaction};
+use std::error::Error;
+
+/* Frequency List Functions */
+
+/// Insert a frequency list, or get the id of the list with this name.
+pub fn insert_list(tx: &Transaction, name: &str) -> Result<i64, Box<dyn Error>> {
+    tx.execute(
+        "INSERT OR IGNORE INTO frequency_lists (name) VALUES (?);",
+        params![name],
+    )?;
+    tx.execute(
+        "UPDATE frequency_lists SET imported_at = strftime('%s', 'now') WHERE name = ?;",
+        params![name],
+    )?;
+
+    let id = tx.query_row(
+        "SELECT id FROM frequency_lists WHERE name = ?;",
+        params![name],
+        |row| row.get(0),
+    )?;
+
+    Ok(id)
+}
+
+/// Delete a frequency list and its entries, returns false if there is no list with this name.
+pub fn delete_list(tx: &Transaction, name: &str) -> Result<bool, Box<dyn Error>> {
+    tx.execute(
+        "DELETE FROM external_frequencies WHERE frequency_list_id IN (SELECT id FROM frequency_lists WHERE name = ?);",
+        params![name],
+    )?;
+    let count = tx.execute("DELETE FROM frequency_lists WHERE name = ?;", params![name])?;
+
+    Ok(count > 0)
+}
+
+pub fn delete_entry_list(tx: &Transaction, id: i64) -> Result<(), Box<dyn Error>> {
+    tx.execute(
+        "DELETE FROM external_frequencies WHERE frequency_list_id = ?;",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+/// Insert the rank of a lemma and reading, keeping the best rank of duplicate entries.
+pub fn insert_entry(
+    tx: &Transaction,
+    id: i64,
+    expression: &str,
+    reading: &str,
+    rank: i64,
+) -> Result<(), Box<dyn Error>> {
+    let query = "INSERT INTO external_frequencies (frequency_list_id, expression, reading, rank) VALUES (?, ?, ?, ?)
+                ON CONFLICT (frequency_list_id, expression, reading) DO UPDATE SET rank = MIN(rank, excluded.rank);";
+
+    tx.prepare_cached(query)?
+        .execute(params![id, expression, reading, rank])?;
+
+    Ok(())
+}
+
+/// Get every frequency list with its number of entries.
+pub fn select_list(conn: &Connection) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    let query = "SELECT name, COUNT(frequency_list_id)
+                FROM frequency_lists LEFT JOIN external_frequencies ON frequency_list_id = frequency_lists.id
+                GROUP BY frequency_lists.id
+                ORDER BY name ASC;";
+
+    let mut statement = conn.prepare(query)?;
+    let list = statement
+        .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+    Ok(list)
+}
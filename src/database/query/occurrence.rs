@@ -0,0 +1,30 @@
+use rusqlite::{params, Transaction};
+use std::error::Error;
+
+/* Occurrence Functions */
+
+/// Insert an occurrence and return whether it is new.
+///
+/// Occurrences are keyed by sentence and token position,
+/// so importing a sentence a second time does not count its tokens again.
+pub fn insert(
+    tx: &Transaction,
+    sentence_id: i64,
+    position: i64,
+    expression_id: i64,
+    pos_id: i64,
+    surface_string_id: i64,
+) -> Result<bool, Box<dyn Error>> {
+    let params = params![
+        sentence_id,
+        position,
+        expression_id,
+        pos_id,
+        surface_string_id
+    ];
+    let query = "INSERT OR IGNORE INTO occurrences (sentence_id, position, expression_id, pos_id, surface_string_id) VALUES (?, ?, ?, ?, ?);";
+
+    let count = tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(count > 0)
+}
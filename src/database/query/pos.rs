@@ -1,21 +1,24 @@
-use rusqlite::{params, Transaction};
+use rusqlite::{params, OptionalExtension, Transaction};
 use std::error::Error;
 
 /* Pos Functions */
 
-pub fn insert(tx: &Transaction, string: &str) -> Result<(), Box<dyn Error>> {
+pub fn insert(tx: &Transaction, string: &str) -> Result<i64, Box<dyn Error>> {
     let params = params![string];
-    let query = "INSERT OR IGNORE INTO pos (pos) VALUES (?);";
-    tx.execute(query, params)?;
+    let query = "INSERT INTO pos (pos) VALUES (?);";
+    tx.prepare_cached(query)?.execute(params)?;
 
-    Ok(())
+    Ok(tx.last_insert_rowid())
 }
 
-pub fn select_id(tx: &Transaction, string: &str) -> Result<i32, Box<dyn Error>> {
+pub fn select_id(tx: &Transaction, string: &str) -> Result<Option<i64>, Box<dyn Error>> {
     let params = params![string];
     let query = "SELECT id FROM pos WHERE pos = ?;";
 
-    let id: i32 = tx.query_row(query, params, |row| row.get(0))?;
+    let id: Option<i64> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| row.get(0))
+        .optional()?;
 
     Ok(id)
 }
@@ -1,30 +1,53 @@
-use rusqlite::{params, Connection, Transaction};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
 use std::error::Error;
 
 /* Sentence Functions */
 
-pub fn insert(tx: &Transaction, string: &str) -> Result<(), Box<dyn Error>> {
+pub fn insert(tx: &Transaction, string: &str) -> Result<i64, Box<dyn Error>> {
     let params = params![string];
-    let query = "INSERT OR IGNORE INTO sentences (sentence) VALUES (?);";
+    let query = "INSERT INTO sentences (sentence) VALUES (?);";
 
-    tx.execute(query, params)?;
+    tx.prepare_cached(query)?.execute(params)?;
 
-    Ok(())
+    Ok(tx.last_insert_rowid())
 }
 
-pub fn select_id(tx: &Transaction, string: &str) -> Result<i32, Box<dyn Error>> {
+pub fn select_id(tx: &Transaction, string: &str) -> Result<Option<i64>, Box<dyn Error>> {
     let params = params![string];
     let query = "SELECT id FROM sentences WHERE sentence = ?;";
 
-    let id: i32 = tx.query_row(query, params, |row| row.get(0))?;
+    let id: Option<i64> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| row.get(0))
+        .optional()?;
 
     Ok(id)
 }
 
 pub fn exists(conn: &Connection, string: &str) -> Result<bool, Box<dyn Error>> {
     let params = params![string];
-    let mut query = conn.prepare("SELECT id FROM sentences WHERE sentence = ?;")?;
+    let mut query = conn.prepare_cached("SELECT id FROM sentences WHERE sentence = ?;")?;
     let exists = query.exists(params)?;
 
     Ok(exists)
 }
+
+/// Rebuild the full-text index entry of a sentence from its occurrences.
+pub fn update_fts(tx: &Transaction, id: i64) -> Result<(), Box<dyn Error>> {
+    tx.prepare_cached("DELETE FROM sentences_fts WHERE rowid = ?;")?
+        .execute(params![id])?;
+
+    let query = "INSERT INTO sentences_fts (rowid, surface_string_list)
+                SELECT sentence_id, group_concat(surface_string, ' ')
+                FROM (
+                    SELECT sentence_id, surface_string
+                    FROM occurrences JOIN surface_strings ON surface_strings.id = surface_string_id
+                    WHERE sentence_id = ?
+                    ORDER BY position ASC
+                )
+                GROUP BY sentence_id;";
+
+    tx.prepare_cached(query)?.execute(params![id])?;
+
+    Ok(())
+}
@@ -86,3 +86,363 @@ pub fn create_expressions_pos_sentences_surface_strings(
 
     Ok(())
 }
+
+pub fn create_occurrences(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS occurrences (
+                sentence_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                expression_id INTEGER NOT NULL,
+                pos_id INTEGER NOT NULL,
+                surface_string_id INTEGER NOT NULL,
+                PRIMARY KEY (sentence_id, position),
+                    FOREIGN KEY (sentence_id)
+                        REFERENCES sentences (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION,
+                    FOREIGN KEY (expression_id)
+                        REFERENCES expressions (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION,
+                    FOREIGN KEY (pos_id)
+                        REFERENCES pos (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION,
+                    FOREIGN KEY (surface_string_id)
+                        REFERENCES surface_strings (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS occurrences_expression_id ON occurrences (expression_id);",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Replace the join table with a view over occurrences, so existing queries keep working.
+pub fn create_expressions_pos_sentences_surface_strings_view(
+    conn: &Connection,
+) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "DROP TABLE IF EXISTS expressions_pos_sentences_surface_strings;",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE VIEW IF NOT EXISTS expressions_pos_sentences_surface_strings AS
+                SELECT DISTINCT pos_id, sentence_id, expression_id, surface_string_id
+                FROM occurrences;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// A full-text index over sentences.
+///
+/// Japanese is not separated by spaces, so the index holds the surface strings of each sentence
+/// joined by spaces, with the sentence id as the rowid.
+pub fn create_sentences_fts(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS sentences_fts USING fts5(
+                surface_string_list,
+                tokenize = 'unicode61 remove_diacritics 0'
+                );",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// The imported files, identified by their path.
+pub fn create_documents(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS documents (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL UNIQUE,
+                imported_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// The sentences of each document, with the position of their first appearance.
+pub fn create_document_sentences(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS document_sentences (
+                document_id INTEGER NOT NULL,
+                sentence_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                PRIMARY KEY (document_id, sentence_id),
+                    FOREIGN KEY (document_id)
+                        REFERENCES documents (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION,
+                    FOREIGN KEY (sentence_id)
+                        REFERENCES sentences (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS document_sentences_sentence_id ON document_sentences (sentence_id);",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+pub fn create_tags(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY,
+                tag TEXT NOT NULL UNIQUE
+                );",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+pub fn create_expressions_tags(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS expressions_tags (
+                expression_id INTEGER NOT NULL,
+                tag_id INTEGER NOT NULL,
+                PRIMARY KEY (expression_id, tag_id),
+                    FOREIGN KEY (expression_id)
+                        REFERENCES expressions (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION,
+                    FOREIGN KEY (tag_id)
+                        REFERENCES tags (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS expressions_tags_tag_id ON expressions_tags (tag_id);",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// The state-changing commands, a command is open while it runs and its changes are recorded.
+pub fn create_history(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY,
+                command TEXT NOT NULL,
+                created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                is_open INTEGER NOT NULL DEFAULT 1,
+                is_undone INTEGER NOT NULL DEFAULT 0
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS history_changes (
+                id INTEGER PRIMARY KEY,
+                history_id INTEGER NOT NULL,
+                table_name TEXT NOT NULL,
+                row_id INTEGER NOT NULL,
+                column_name TEXT NOT NULL,
+                old_value INTEGER,
+                new_value INTEGER,
+                    FOREIGN KEY (history_id)
+                        REFERENCES history (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS history_changes_history_id ON history_changes (history_id);",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Record the changes to a column of a table in the open history entry.
+pub fn create_history_column_trigger(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER IF NOT EXISTS history_{table}_{column}
+                AFTER UPDATE OF {column} ON {table}
+                WHEN OLD.{column} IS NOT NEW.{column} AND EXISTS (SELECT 1 FROM history WHERE is_open = 1)
+                BEGIN
+                    INSERT INTO history_changes (history_id, table_name, row_id, column_name, old_value, new_value)
+                        SELECT MAX(id), '{table}', NEW.id, '{column}', OLD.{column}, NEW.{column} FROM history WHERE is_open = 1;
+                END;",
+            table = table,
+            column = column
+        ),
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Record the changes made while a history entry is open.
+///
+/// Flags are recorded with their old and new value, new expressions as a `row` column going from 0 to 1,
+/// and tags as a column named after the tag going from 0 to 1 when added and from 1 to 0 when removed.
+pub fn create_history_triggers(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let insert_change = "INSERT INTO history_changes (history_id, table_name, row_id, column_name, old_value, new_value)
+                SELECT MAX(id)";
+    let open_history = "FROM history WHERE is_open = 1";
+
+    for (table, column) in [
+        ("expressions", "is_excluded"),
+        ("expressions", "in_anki"),
+        ("expressions", "is_learned"),
+        ("pos", "is_excluded"),
+    ]
+    .iter()
+    {
+        create_history_column_trigger(conn, table, column)?;
+    }
+
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER IF NOT EXISTS history_expressions_row
+                AFTER INSERT ON expressions
+                WHEN EXISTS (SELECT 1 FROM history WHERE is_open = 1)
+                BEGIN
+                    {}, 'expressions', NEW.id, 'row', 0, 1 {};
+                END;",
+            insert_change, open_history
+        ),
+        params![],
+    )?;
+
+    for (trigger, event, row, old_value, new_value) in [
+        ("insert", "INSERT", "NEW", 0, 1),
+        ("delete", "DELETE", "OLD", 1, 0),
+    ]
+    .iter()
+    {
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS history_expressions_tags_{trigger}
+                AFTER {event} ON expressions_tags
+                WHEN EXISTS (SELECT 1 FROM history WHERE is_open = 1)
+                BEGIN
+                    {insert_change}, 'expressions_tags', {row}.expression_id,
+                        (SELECT tag FROM tags WHERE id = {row}.tag_id), {old_value}, {new_value} {open_history};
+                END;",
+                trigger = trigger,
+                event = event,
+                row = row,
+                old_value = old_value,
+                new_value = new_value,
+                insert_change = insert_change,
+                open_history = open_history
+            ),
+            params![],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The external frequency lists that have been imported.
+pub fn create_frequency_lists(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS frequency_lists (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                imported_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// The rank of each lemma and reading in an external frequency list.
+pub fn create_external_frequencies(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS external_frequencies (
+                frequency_list_id INTEGER NOT NULL,
+                expression TEXT NOT NULL,
+                reading TEXT NOT NULL DEFAULT '',
+                rank INTEGER NOT NULL,
+                PRIMARY KEY (frequency_list_id, expression, reading),
+                    FOREIGN KEY (frequency_list_id)
+                        REFERENCES frequency_lists (id)
+                            ON DELETE CASCADE
+                            ON UPDATE NO ACTION
+                );",
+        params![],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS external_frequencies_expression ON external_frequencies (expression);",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// The manual priority of each expression, used when scoring expressions.
+pub fn create_expressions_priority(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "ALTER TABLE expressions ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Entries that are not reversible are listed in the history and undo stops at them.
+pub fn create_history_reversible(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "ALTER TABLE history ADD COLUMN is_reversible INTEGER NOT NULL DEFAULT 1;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+/// Whether is_learned was set by a sync, so a later sync can clear it after a lapse.
+pub fn create_expressions_learned_by_sync(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "ALTER TABLE expressions ADD COLUMN is_learned_by_sync INTEGER NOT NULL DEFAULT 0;",
+        params![],
+    )?;
+
+    Ok(())
+}
+
+pub fn create_expressions_seen(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "ALTER TABLE expressions ADD COLUMN first_seen INTEGER;",
+        params![],
+    )?;
+    conn.execute(
+        "ALTER TABLE expressions ADD COLUMN last_seen INTEGER;",
+        params![],
+    )?;
+
+    Ok(())
+}
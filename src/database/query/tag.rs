@@ -0,0 +1,99 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::error::Error;
+
+/* Tag Functions */
+
+pub fn insert(tx: &Transaction, string: &str) -> Result<i64, Box<dyn Error>> {
+    let params = params![string];
+    let query = "INSERT INTO tags (tag) VALUES (?);";
+
+    tx.prepare_cached(query)?.execute(params)?;
+
+    Ok(tx.last_insert_rowid())
+}
+
+pub fn select_id(tx: &Transaction, string: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    let params = params![string];
+    let query = "SELECT id FROM tags WHERE tag = ?;";
+
+    let id: Option<i64> = tx
+        .prepare_cached(query)?
+        .query_row(params, |row| row.get(0))
+        .optional()?;
+
+    Ok(id)
+}
+
+/// Tag an expression, returns false if it already had the tag.
+pub fn insert_expression(
+    tx: &Transaction,
+    id: i64,
+    expression_id: i64,
+) -> Result<bool, Box<dyn Error>> {
+    let query = "INSERT OR IGNORE INTO expressions_tags (expression_id, tag_id) VALUES (?, ?);";
+
+    let count = tx
+        .prepare_cached(query)?
+        .execute(params![expression_id, id])?;
+
+    Ok(count > 0)
+}
+
+/// Remove the tag from an expression, returns false if it did not have the tag.
+pub fn delete_expression(
+    tx: &Transaction,
+    id: i64,
+    expression_id: i64,
+) -> Result<bool, Box<dyn Error>> {
+    let query = "DELETE FROM expressions_tags WHERE expression_id = ? AND tag_id = ?;";
+
+    let count = tx
+        .prepare_cached(query)?
+        .execute(params![expression_id, id])?;
+
+    Ok(count > 0)
+}
+
+/// Delete the tags that are not on any expression.
+pub fn delete_unused(tx: &Transaction) -> Result<usize, Box<dyn Error>> {
+    let query = "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM expressions_tags);";
+
+    let count = tx.execute(query, params![])?;
+
+    Ok(count)
+}
+
+/// Get every tag with the number of expressions that have it.
+pub fn select_list(conn: &Connection) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    let query = "SELECT tag, COUNT(expression_id)
+                FROM tags LEFT JOIN expressions_tags ON tag_id = tags.id
+                GROUP BY tags.id
+                ORDER BY tag ASC;";
+
+    let mut statement = conn.prepare(query)?;
+    let tag_list = statement
+        .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+    Ok(tag_list)
+}
+
+/// Get the tags of an expression.
+pub fn select_list_for_expression(
+    conn: &Connection,
+    expression: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let query = "SELECT tag
+                FROM tags
+                JOIN expressions_tags ON tag_id = tags.id
+                JOIN expressions ON expressions.id = expression_id
+                WHERE expression = ?
+                ORDER BY tag ASC;";
+
+    let mut statement = conn.prepare_cached(query)?;
+    let tag_list = statement
+        .query_map(params![expression], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(tag_list)
+}
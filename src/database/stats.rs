@@ -0,0 +1,747 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::error::Error;
+
+/// The coverage targets reported by `stats`.
+pub const TARGET_LIST: &[f64] = &[0.90, 0.95, 0.98];
+
+/// The running tokens of a part of the corpus and how many of them are known.
+///
+/// Tokens of excluded expressions are counted separately and left out of the coverage,
+/// since they are never turned into cards.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Coverage {
+    pub token_count: i64,
+    pub excluded_token_count: i64,
+    pub known_token_count: i64,
+    pub coverage: f64,
+}
+
+impl Coverage {
+    pub fn new(token_count: i64, excluded_token_count: i64, known_token_count: i64) -> Coverage {
+        let counted_token_count = token_count - excluded_token_count;
+        let coverage = match counted_token_count {
+            0 => 0.0,
+            _ => known_token_count as f64 / counted_token_count as f64,
+        };
+
+        Coverage {
+            token_count,
+            excluded_token_count,
+            known_token_count,
+            coverage,
+        }
+    }
+
+    /// The number of tokens that are neither excluded nor known.
+    pub fn unknown_token_count(&self) -> i64 {
+        self.token_count - self.excluded_token_count - self.known_token_count
+    }
+
+    /// Whether the known tokens reach target, a share between 0 and 1.
+    pub fn is_reached(&self, known_token_count: i64, target: f64) -> bool {
+        let counted_token_count = (self.token_count - self.excluded_token_count) as f64;
+
+        known_token_count as f64 >= target * counted_token_count - 1e-9
+    }
+}
+
+/// The coverage of a document or pos.
+#[derive(Debug, Serialize)]
+pub struct NamedCoverage {
+    pub name: String,
+    #[serde(flatten)]
+    pub coverage: Coverage,
+}
+
+/// The number of expressions that have to be learned to reach a coverage target.
+#[derive(Debug, Serialize)]
+pub struct Target {
+    pub target: f64,
+    /// None if the target cannot be reached by learning the unknown expressions
+    pub cards_needed: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub token_count: i64,
+    pub expression_count: i64,
+    pub sentence_count: i64,
+    pub document_count: i64,
+    pub excluded_count: i64,
+    pub in_anki_count: i64,
+    pub learned_count: i64,
+    pub coverage: Coverage,
+    pub target_list: Vec<Target>,
+    pub document_list: Vec<NamedCoverage>,
+    pub pos_list: Vec<NamedCoverage>,
+}
+
+/// The columns of a Coverage, selected from occurrences joined with expressions as `e`.
+const COVERAGE_COLUMNS: &str = "COUNT(*),
+                IFNULL(SUM(IFNULL(e.is_excluded, 0) = 1), 0),
+                IFNULL(SUM(IFNULL(e.is_excluded, 0) = 0 AND (e.is_learned = 1 OR e.in_anki = 1)), 0)";
+
+/// An expression counts as unknown if it is neither excluded, learned nor in anki.
+pub const IS_UNKNOWN: &str = "IFNULL(e.is_excluded, 0) = 0 AND e.is_learned = 0 AND e.in_anki = 0";
+
+fn count(conn: &Connection, query: &str) -> Result<i64, Box<dyn Error>> {
+    let count = conn.query_row(query, params![], |row| row.get(0))?;
+
+    Ok(count)
+}
+
+fn select_coverage(conn: &Connection) -> Result<Coverage, Box<dyn Error>> {
+    let query = format!(
+        "SELECT {} FROM occurrences AS o JOIN expressions AS e ON e.id = o.expression_id;",
+        COVERAGE_COLUMNS
+    );
+
+    let coverage = conn.query_row(&query, params![], |row| {
+        Ok(Coverage::new(row.get(0)?, row.get(1)?, row.get(2)?))
+    })?;
+
+    Ok(coverage)
+}
+
+fn select_named_coverage_list(
+    conn: &Connection,
+    query: &str,
+) -> Result<Vec<NamedCoverage>, Box<dyn Error>> {
+    let mut statement = conn.prepare(query)?;
+    let coverage_list = statement
+        .query_map(params![], |row| {
+            Ok(NamedCoverage {
+                name: row.get(0)?,
+                coverage: Coverage::new(row.get(1)?, row.get(2)?, row.get(3)?),
+            })
+        })?
+        .collect::<Result<Vec<NamedCoverage>, _>>()?;
+
+    Ok(coverage_list)
+}
+
+/// Get the coverage of every document, ordered by name.
+pub fn select_document_coverage_list(
+    conn: &Connection,
+) -> Result<Vec<NamedCoverage>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT d.name, {}
+                FROM documents AS d
+                JOIN document_sentences AS ds ON ds.document_id = d.id
+                JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                JOIN expressions AS e ON e.id = o.expression_id
+                GROUP BY d.id
+                ORDER BY d.name ASC, d.id ASC;",
+        COVERAGE_COLUMNS
+    );
+
+    select_named_coverage_list(conn, &query)
+}
+
+/// Get the coverage of every pos, ordered by the number of tokens.
+pub fn select_pos_coverage_list(conn: &Connection) -> Result<Vec<NamedCoverage>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT p.pos, {}
+                FROM occurrences AS o
+                JOIN expressions AS e ON e.id = o.expression_id
+                JOIN pos AS p ON p.id = o.pos_id
+                GROUP BY p.id
+                ORDER BY COUNT(*) DESC, p.pos ASC;",
+        COVERAGE_COLUMNS
+    );
+
+    select_named_coverage_list(conn, &query)
+}
+
+/// Get the number of tokens of each unknown expression, most frequent first.
+fn select_unknown_token_count_list(conn: &Connection) -> Result<Vec<i64>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT COUNT(*)
+                FROM occurrences AS o JOIN expressions AS e ON e.id = o.expression_id
+                WHERE {}
+                GROUP BY e.id
+                ORDER BY COUNT(*) DESC;",
+        IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let count_list = statement
+        .query_map(params![], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+
+    Ok(count_list)
+}
+
+/// How much of a document is unknown with the current learned, in anki and excluded flags.
+#[derive(Debug, Serialize)]
+pub struct Difficulty {
+    pub id: i64,
+    pub name: String,
+    #[serde(flatten)]
+    pub coverage: Coverage,
+    /// the number of distinct expressions that are not excluded
+    pub expression_count: i64,
+    pub unknown_expression_count: i64,
+}
+
+impl Difficulty {
+    /// The share of the counted tokens that are unknown.
+    pub fn unknown_token_share(&self) -> f64 {
+        1.0 - self.coverage.coverage
+    }
+
+    /// The share of the distinct expressions that are unknown.
+    pub fn unknown_expression_share(&self) -> f64 {
+        match self.expression_count {
+            0 => 1.0,
+            _ => self.unknown_expression_count as f64 / self.expression_count as f64,
+        }
+    }
+}
+
+/// Get the difficulty of every document, easiest first.
+///
+/// Documents are ranked by their share of unknown tokens, then by their share of unknown expressions.
+pub fn select_document_difficulty_list(
+    conn: &Connection,
+) -> Result<Vec<Difficulty>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT d.id, d.name, {},
+                COUNT(DISTINCT CASE WHEN IFNULL(e.is_excluded, 0) = 0 THEN e.id END),
+                COUNT(DISTINCT CASE WHEN {} THEN e.id END)
+                FROM documents AS d
+                JOIN document_sentences AS ds ON ds.document_id = d.id
+                JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                JOIN expressions AS e ON e.id = o.expression_id
+                GROUP BY d.id;",
+        COVERAGE_COLUMNS, IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let mut difficulty_list = statement
+        .query_map(params![], |row| {
+            Ok(Difficulty {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                coverage: Coverage::new(row.get(2)?, row.get(3)?, row.get(4)?),
+                expression_count: row.get(5)?,
+                unknown_expression_count: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<Difficulty>, _>>()?;
+
+    difficulty_list.sort_by(|a, b| {
+        a.unknown_token_share()
+            .partial_cmp(&b.unknown_token_share())
+            .unwrap_or(Ordering::Equal)
+            .then(
+                a.unknown_expression_share()
+                    .partial_cmp(&b.unknown_expression_share())
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(a.name.cmp(&b.name))
+    });
+
+    Ok(difficulty_list)
+}
+
+/// Get the coverage of a document.
+pub fn select_document_coverage(conn: &Connection, id: i64) -> Result<Coverage, Box<dyn Error>> {
+    let query = format!(
+        "SELECT {}
+                FROM document_sentences AS ds
+                JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                JOIN expressions AS e ON e.id = o.expression_id
+                WHERE ds.document_id = ?;",
+        COVERAGE_COLUMNS
+    );
+
+    let coverage = conn.query_row(&query, params![id], |row| {
+        Ok(Coverage::new(row.get(0)?, row.get(1)?, row.get(2)?))
+    })?;
+
+    Ok(coverage)
+}
+
+/// Get the unknown expressions of a document with their number of tokens in it, most frequent first.
+pub fn select_unknown_expression_list_for_document(
+    conn: &Connection,
+    id: i64,
+) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT e.expression, COUNT(*)
+                FROM document_sentences AS ds
+                JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                JOIN expressions AS e ON e.id = o.expression_id
+                WHERE ds.document_id = ? AND {}
+                GROUP BY e.id
+                ORDER BY COUNT(*) DESC, e.expression ASC;",
+        IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let expression_list = statement
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+    Ok(expression_list)
+}
+
+/// Count the expressions that have to be learned to reach target, learning the most frequent first.
+///
+/// # Arguments
+///
+/// * `coverage` - The current coverage
+/// * `unknown_token_count_list` - The number of tokens of each unknown expression, most frequent first
+/// * `target` - The coverage to reach, a share between 0 and 1
+pub fn cards_needed(
+    coverage: &Coverage,
+    unknown_token_count_list: &[i64],
+    target: f64,
+) -> Option<usize> {
+    let mut known_token_count = coverage.known_token_count;
+
+    for (i, token_count) in unknown_token_count_list.iter().enumerate() {
+        if coverage.is_reached(known_token_count, target) {
+            return Some(i);
+        }
+
+        known_token_count += token_count;
+    }
+
+    match coverage.is_reached(known_token_count, target) {
+        true => Some(unknown_token_count_list.len()),
+        false => None,
+    }
+}
+
+/// An expression that is more frequent in a document than in the rest of the corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub expression: String,
+    /// the number of tokens in the document
+    pub count: i64,
+    /// the number of tokens in the rest of the corpus
+    pub reference_count: i64,
+    /// the log-likelihood keyness, higher is more distinctive
+    pub log_likelihood: f64,
+}
+
+/// The log-likelihood (G2) of a count in a document against a count in a reference corpus.
+///
+/// # Arguments
+///
+/// * `count` - The number of tokens of the expression in the document
+/// * `reference_count` - The number of tokens of the expression in the reference corpus
+/// * `size` - The number of tokens of the document
+/// * `reference_size` - The number of tokens of the reference corpus
+pub fn log_likelihood(count: i64, reference_count: i64, size: i64, reference_size: i64) -> f64 {
+    let total = (size + reference_size) as f64;
+    if total == 0.0 {
+        return 0.0;
+    }
+
+    let expected = size as f64 * (count + reference_count) as f64 / total;
+    let reference_expected = reference_size as f64 * (count + reference_count) as f64 / total;
+
+    let term = |observed: i64, expected: f64| match observed > 0 {
+        true => observed as f64 * (observed as f64 / expected).ln(),
+        false => 0.0,
+    };
+
+    2.0 * (term(count, expected) + term(reference_count, reference_expected))
+}
+
+/// Get the unknown keywords of a document against the rest of the corpus, most distinctive first.
+///
+/// Only expressions with at least min_count tokens in the document that are relatively more frequent in it than in the rest are keywords.
+pub fn select_keyword_list(
+    conn: &Connection,
+    id: i64,
+    min_count: i64,
+) -> Result<Vec<Keyword>, Box<dyn Error>> {
+    let size: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM document_sentences AS ds JOIN occurrences AS o ON o.sentence_id = ds.sentence_id WHERE ds.document_id = ?;",
+        params![id],
+        |row| row.get(0),
+    )?;
+    let reference_size = (count(conn, "SELECT COUNT(*) FROM occurrences;")? - size).max(0);
+
+    let query = format!(
+        "SELECT e.expression, COUNT(*), e.frequency
+                FROM document_sentences AS ds
+                JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                JOIN expressions AS e ON e.id = o.expression_id
+                WHERE ds.document_id = ? AND {}
+                GROUP BY e.id
+                HAVING COUNT(*) >= ?;",
+        IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let mut keyword_list = statement
+        .query_map(params![id, min_count], |row| {
+            let count: i64 = row.get(1)?;
+            let frequency: i64 = row.get(2)?;
+
+            Ok((row.get::<_, String>(0)?, count, (frequency - count).max(0)))
+        })?
+        .collect::<Result<Vec<(String, i64, i64)>, _>>()?
+        .into_iter()
+        // compare the relative frequencies without dividing by an empty reference corpus
+        .filter(|(_, count, reference_count)| count * reference_size > reference_count * size)
+        .map(|(expression, count, reference_count)| Keyword {
+            log_likelihood: log_likelihood(count, reference_count, size, reference_size),
+            expression,
+            count,
+            reference_count,
+        })
+        .collect::<Vec<Keyword>>();
+
+    keyword_list.sort_by(|a, b| {
+        b.log_likelihood
+            .partial_cmp(&a.log_likelihood)
+            .unwrap_or(Ordering::Equal)
+            .then(a.expression.cmp(&b.expression))
+    });
+
+    Ok(keyword_list)
+}
+
+/// The first occurrence of an unknown expression in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirstOccurrence {
+    pub expression: String,
+    /// the position of the first sentence the expression occurs in
+    pub position: i64,
+    /// the number of tokens of the expression in the document
+    pub count: i64,
+    /// the first sentence the expression occurs in
+    pub sentence: String,
+}
+
+/// Get the sentences of a document with their positions, in document order.
+pub fn select_document_sentence_list(
+    conn: &Connection,
+    id: i64,
+) -> Result<Vec<(i64, String)>, Box<dyn Error>> {
+    let query = "SELECT ds.position, s.sentence
+                FROM document_sentences AS ds
+                JOIN sentences AS s ON s.id = ds.sentence_id
+                WHERE ds.document_id = ?
+                ORDER BY ds.position ASC;";
+
+    let mut statement = conn.prepare(query)?;
+    let sentence_list = statement
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(i64, String)>, _>>()?;
+
+    Ok(sentence_list)
+}
+
+/// Get the first occurrence of every unknown expression that recurs at least min_count times after it in a document, in document order.
+pub fn select_first_occurrence_list(
+    conn: &Connection,
+    id: i64,
+    min_count: i64,
+) -> Result<Vec<FirstOccurrence>, Box<dyn Error>> {
+    let query = format!(
+        "SELECT expression, sentence_position, count, sentence
+                FROM (
+                    SELECT e.expression, ds.position AS sentence_position, s.sentence,
+                        ROW_NUMBER() OVER (PARTITION BY e.id ORDER BY ds.position, o.position) AS number,
+                        COUNT(*) OVER (PARTITION BY e.id) AS count,
+                        o.position AS token_position
+                    FROM document_sentences AS ds
+                    JOIN occurrences AS o ON o.sentence_id = ds.sentence_id
+                    JOIN expressions AS e ON e.id = o.expression_id
+                    JOIN sentences AS s ON s.id = ds.sentence_id
+                    WHERE ds.document_id = ? AND {}
+                )
+                WHERE number = 1 AND count - 1 >= ?
+                ORDER BY sentence_position ASC, token_position ASC;",
+        IS_UNKNOWN
+    );
+
+    let mut statement = conn.prepare(&query)?;
+    let occurrence_list = statement
+        .query_map(params![id, min_count], |row| {
+            Ok(FirstOccurrence {
+                expression: row.get(0)?,
+                position: row.get(1)?,
+                count: row.get(2)?,
+                sentence: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<FirstOccurrence>, _>>()?;
+
+    Ok(occurrence_list)
+}
+
+/// Collect the corpus statistics.
+pub fn select_stats(conn: &Connection) -> Result<Stats, Box<dyn Error>> {
+    let coverage = select_coverage(conn)?;
+    let unknown_token_count_list = select_unknown_token_count_list(conn)?;
+
+    let target_list = TARGET_LIST
+        .iter()
+        .map(|target| Target {
+            target: *target,
+            cards_needed: cards_needed(&coverage, &unknown_token_count_list, *target),
+        })
+        .collect();
+
+    Ok(Stats {
+        token_count: coverage.token_count,
+        expression_count: count(
+            conn,
+            "SELECT COUNT(DISTINCT expression_id) FROM occurrences;",
+        )?,
+        sentence_count: count(conn, "SELECT COUNT(*) FROM sentences;")?,
+        document_count: count(conn, "SELECT COUNT(*) FROM documents;")?,
+        excluded_count: count(
+            conn,
+            "SELECT COUNT(*) FROM expressions WHERE is_excluded = 1;",
+        )?,
+        in_anki_count: count(conn, "SELECT COUNT(*) FROM expressions WHERE in_anki = 1;")?,
+        learned_count: count(
+            conn,
+            "SELECT COUNT(*) FROM expressions WHERE is_learned = 1;",
+        )?,
+        coverage,
+        target_list,
+        document_list: select_document_coverage_list(conn)?,
+        pos_list: select_pos_coverage_list(conn)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage() {
+        let coverage = Coverage::new(12, 2, 5);
+
+        assert_eq!(coverage.coverage, 0.5);
+        assert_eq!(coverage.unknown_token_count(), 5);
+        assert_eq!(Coverage::new(0, 0, 0).coverage, 0.0);
+    }
+
+    #[test]
+    fn test_cards_needed() {
+        let coverage = Coverage::new(20, 0, 10);
+        let unknown_token_count_list = vec![5, 3, 1, 1];
+
+        assert_eq!(
+            cards_needed(&coverage, &unknown_token_count_list, 0.5),
+            Some(0)
+        );
+        assert_eq!(
+            cards_needed(&coverage, &unknown_token_count_list, 0.75),
+            Some(1)
+        );
+        assert_eq!(
+            cards_needed(&coverage, &unknown_token_count_list, 0.9),
+            Some(2)
+        );
+        assert_eq!(
+            cards_needed(&coverage, &unknown_token_count_list, 1.0),
+            Some(4)
+        );
+
+        let coverage = Coverage::new(20, 0, 0);
+        assert_eq!(cards_needed(&coverage, &[5], 0.5), None);
+    }
+
+    #[test]
+    fn test_log_likelihood() {
+        assert!((log_likelihood(10, 10, 1000, 10000) - 22.138).abs() < 1e-3);
+        // the same relative frequency in both is not key
+        assert!(log_likelihood(1, 10, 100, 1000).abs() < 1e-9);
+        assert_eq!(log_likelihood(0, 0, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn test_select_stats() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        super::super::migration::migrate(&mut conn, None).unwrap();
+
+        let expression = |expression: &str, pos: &str, sentence: &str, position: usize| {
+            crate::Expression::new(expression.to_string())
+                .pos(vec![pos.to_string()])
+                .sentence(vec![sentence.to_string()])
+                .surface_string(vec![expression.to_string()])
+                .position(position)
+        };
+
+        super::super::insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "名詞", "猫と犬", 0),
+                expression("と", "助詞", "猫と犬", 1),
+                expression("犬", "名詞", "猫と犬", 2),
+                expression("猫", "名詞", "猫だ", 0),
+            ],
+            &|| {},
+        )
+        .unwrap();
+        super::super::insert_document(
+            &mut conn,
+            "a.txt",
+            "/tmp/a.txt",
+            &["猫だ".to_string(), "鳥だ".to_string()],
+            0,
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE expressions SET is_excluded = 1 WHERE expression = 'と';",
+            params![],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE expressions SET is_learned = 1 WHERE expression = '犬';",
+            params![],
+        )
+        .unwrap();
+
+        let stats = select_stats(&conn).unwrap();
+
+        assert_eq!(stats.token_count, 4);
+        assert_eq!(stats.expression_count, 3);
+        assert_eq!(stats.sentence_count, 2);
+        assert_eq!(stats.document_count, 1);
+        assert_eq!(stats.coverage, Coverage::new(4, 1, 1));
+        assert_eq!(stats.target_list[0].cards_needed, Some(1));
+        assert_eq!(stats.document_list[0].name, "a.txt");
+        assert_eq!(stats.document_list[0].coverage, Coverage::new(1, 0, 0));
+        assert_eq!(stats.pos_list[0].name, "名詞");
+        assert_eq!(stats.pos_list[0].coverage, Coverage::new(3, 0, 1));
+    }
+
+    /// Two documents of one sentence each, a.txt of 猫と猫 and b.txt of 犬と鳥, with nothing learned.
+    fn connect_two_documents() -> rusqlite::Connection {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        super::super::migration::migrate(&mut conn, None).unwrap();
+
+        let expression = |expression: &str, sentence: &str, position: usize| {
+            crate::Expression::new(expression.to_string())
+                .pos(vec!["名詞".to_string()])
+                .sentence(vec![sentence.to_string()])
+                .surface_string(vec![expression.to_string()])
+                .position(position)
+        };
+
+        super::super::insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "猫と猫", 0),
+                expression("と", "猫と猫", 1),
+                expression("猫", "猫と猫", 2),
+                expression("犬", "犬と鳥", 0),
+                expression("と", "犬と鳥", 1),
+                expression("鳥", "犬と鳥", 2),
+            ],
+            &|| {},
+        )
+        .unwrap();
+        super::super::insert_document(&mut conn, "a.txt", "/tmp/a.txt", &["猫と猫".to_string()], 0)
+            .unwrap();
+        super::super::insert_document(&mut conn, "b.txt", "/tmp/b.txt", &["犬と鳥".to_string()], 0)
+            .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_select_document_difficulty_list() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        super::super::migration::migrate(&mut conn, None).unwrap();
+
+        let expression = |expression: &str, sentence: &str, position: usize| {
+            crate::Expression::new(expression.to_string())
+                .pos(vec!["名詞".to_string()])
+                .sentence(vec![sentence.to_string()])
+                .surface_string(vec![expression.to_string()])
+                .position(position)
+        };
+
+        super::super::insert_expression_list(
+            &mut conn,
+            vec![
+                expression("猫", "猫と猫", 0),
+                expression("と", "猫と猫", 1),
+                expression("猫", "猫と猫", 2),
+                expression("犬", "犬と鳥", 0),
+                expression("と", "犬と鳥", 1),
+                expression("鳥", "犬と鳥", 2),
+            ],
+            &|| {},
+        )
+        .unwrap();
+        super::super::insert_document(&mut conn, "a.txt", "/tmp/a.txt", &["猫と猫".to_string()], 0)
+            .unwrap();
+        super::super::insert_document(&mut conn, "b.txt", "/tmp/b.txt", &["犬と鳥".to_string()], 0)
+            .unwrap();
+        conn.execute(
+            "UPDATE expressions SET is_learned = 1 WHERE expression IN ('猫', 'と');",
+            params![],
+        )
+        .unwrap();
+
+        let difficulty_list = select_document_difficulty_list(&conn).unwrap();
+
+        assert_eq!(difficulty_list[0].name, "a.txt");
+        assert_eq!(difficulty_list[0].unknown_token_share(), 0.0);
+        assert_eq!(difficulty_list[1].name, "b.txt");
+        assert_eq!(difficulty_list[1].unknown_expression_count, 2);
+
+        let expression_list =
+            select_unknown_expression_list_for_document(&conn, difficulty_list[1].id).unwrap();
+        assert_eq!(
+            expression_list,
+            vec![("犬".to_string(), 1), ("鳥".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_select_keyword_list() {
+        let conn = connect_two_documents();
+        let id = super::super::select_document_id(&conn, "a.txt").unwrap();
+
+        // と is as frequent in a.txt as in b.txt
+        let keyword_list = select_keyword_list(&conn, id, 1).unwrap();
+        assert_eq!(keyword_list.len(), 1);
+        assert_eq!(keyword_list[0].expression, "猫");
+        assert_eq!(keyword_list[0].count, 2);
+        assert_eq!(keyword_list[0].reference_count, 0);
+
+        assert!(select_keyword_list(&conn, id, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_select_first_occurrence_list() {
+        let conn = connect_two_documents();
+        let id = super::super::select_document_id(&conn, "b.txt").unwrap();
+
+        let occurrence_list = select_first_occurrence_list(&conn, id, 0).unwrap();
+        let expression_list: Vec<&str> = occurrence_list
+            .iter()
+            .map(|x| x.expression.as_str())
+            .collect();
+        assert_eq!(expression_list, vec!["犬", "と", "鳥"]);
+        assert_eq!(occurrence_list[0].sentence, "犬と鳥");
+
+        // only 猫 recurs after its first occurrence
+        let id = super::super::select_document_id(&conn, "a.txt").unwrap();
+        let occurrence_list = select_first_occurrence_list(&conn, id, 1).unwrap();
+        assert_eq!(occurrence_list.len(), 1);
+        assert_eq!(occurrence_list[0].expression, "猫");
+        assert_eq!(occurrence_list[0].count, 2);
+        assert!(select_first_occurrence_list(&conn, id, 2)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Count the days from 1970-01-01 to the date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let is_leap_year = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+
+    match month {
+        2 if is_leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The current time as a unix timestamp.
+pub fn now() -> Result<i64, Box<dyn Error>> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)
+}
+
+/// Parse a date as a unix timestamp.
+///
+/// The date is either YYYY-MM-DD, which is midnight UTC of that day,
+/// or a number of days or weeks before now like 7d or 2w.
+pub fn parse(string: &str, now: i64) -> Result<i64, Box<dyn Error>> {
+    let invalid = || format!("Invalid date: {}, use YYYY-MM-DD, 7d or 2w", string);
+
+    if let Some(unit) = string.chars().last().filter(|c| *c == 'd' || *c == 'w') {
+        let count = string[..string.len() - 1]
+            .parse::<u32>()
+            .map_err(|_| invalid())?;
+        let days = match unit {
+            'w' => count as i64 * 7,
+            _ => count as i64,
+        };
+
+        return Ok(now - days * SECONDS_PER_DAY);
+    }
+
+    let part_list: Vec<&str> = string.split('-').collect();
+    let (year, month, day) = match &part_list[..] {
+        [year, month, day] if year.len() == 4 && month.len() == 2 && day.len() == 2 => (
+            year.parse::<i64>().map_err(|_| invalid())?,
+            month.parse::<i64>().map_err(|_| invalid())?,
+            day.parse::<i64>().map_err(|_| invalid())?,
+        ),
+        _ => return Err(invalid().into()),
+    };
+
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(invalid().into());
+    }
+
+    Ok(days_from_civil(year, month, day) * SECONDS_PER_DAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("1970-01-01", 0).unwrap(), 0);
+        assert_eq!(parse("2000-03-01", 0).unwrap(), 951868800);
+        assert_eq!(parse("2024-02-29", 0).unwrap(), 1709164800);
+        assert_eq!(parse("7d", 1000000).unwrap(), 1000000 - 7 * 86400);
+        assert_eq!(parse("2w", 2000000).unwrap(), 2000000 - 14 * 86400);
+
+        assert!(parse("2023-02-29", 0).is_err());
+        assert!(parse("2024-13-01", 0).is_err());
+        assert!(parse("yesterday", 0).is_err());
+        assert!(parse("-3d", 0).is_err());
+    }
+}
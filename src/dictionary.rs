@@ -1,3 +1,4 @@
+use crate::error::{self, Context};
 use rusqlite::{params, Connection, OpenFlags};
 use std::error::Error;
 use std::path::PathBuf;
@@ -35,7 +36,13 @@ impl DictionaryDefinition {
 }
 
 pub fn connect(path: &PathBuf) -> Result<Connection, Box<dyn Error>> {
-    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).context(
+        error::Kind::Dictionary,
+        &format!(
+            "Failed to open {}, check dictionary_path in the config",
+            path.display()
+        ),
+    )?;
 
     Ok(conn)
 }
@@ -43,6 +50,18 @@ pub fn connect(path: &PathBuf) -> Result<Connection, Box<dyn Error>> {
 pub fn select_definition_for_expression(
     conn: &Connection,
     expression: &str,
+) -> Result<(Vec<DictionaryDefinition>, bool), Box<dyn Error>> {
+    let definition_list = query_definition_for_expression(conn, expression).context(
+        error::Kind::Dictionary,
+        &format!("Failed to look up the definitions of {}", expression),
+    )?;
+
+    Ok(definition_list)
+}
+
+fn query_definition_for_expression(
+    conn: &Connection,
+    expression: &str,
 ) -> Result<(Vec<DictionaryDefinition>, bool), Box<dyn Error>> {
     let mut is_specific = true;
     let params = params![expression];
@@ -51,8 +70,7 @@ pub fn select_definition_for_expression(
     let mut statement = conn.prepare(SELECT_SENSE_ID_FOR_KEB)?;
     let mut id_list: Vec<i32> = statement
         .query_map(params, |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     // if the keb query returned 0 rows, try the reb query
     if id_list.len() == 0 {
@@ -60,8 +78,7 @@ pub fn select_definition_for_expression(
         let mut statement = conn.prepare(SELECT_SENSE_ID_FOR_REB)?;
         id_list = statement
             .query_map(params, |row| Ok(row.get(0)?))?
-            .map(|x| x.unwrap())
-            .collect();
+            .collect::<Result<_, _>>()?;
     }
 
     let mut select_gloss = conn.prepare(SELECT_GLOSS_FOR_SENSE_ID)?;
@@ -71,13 +88,11 @@ pub fn select_definition_for_expression(
     for id in id_list {
         let gloss_list: Vec<String> = select_gloss
             .query_map(params![id], |row| Ok(row.get(0)?))?
-            .map(|x| x.unwrap())
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         let pos_list: Vec<String> = select_pos
             .query_map(params![id], |row| Ok(row.get(0)?))?
-            .map(|x| x.unwrap())
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         definition_list.push(DictionaryDefinition::new(gloss_list, pos_list));
     }
@@ -125,14 +140,25 @@ pub fn filter_definition_with_pos_list(
 pub fn select_reading_for_expression(
     conn: &Connection,
     expression: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let reading_list = query_reading_for_expression(conn, expression).context(
+        error::Kind::Dictionary,
+        &format!("Failed to look up the readings of {}", expression),
+    )?;
+
+    Ok(reading_list)
+}
+
+fn query_reading_for_expression(
+    conn: &Connection,
+    expression: &str,
 ) -> Result<Vec<String>, Box<dyn Error>> {
     let params = params![expression];
 
     let mut statement = conn.prepare(SELECT_READING_FOR_KEB)?;
     let reading_list: Vec<String> = statement
         .query_map(params, |row| Ok(row.get(0)?))?
-        .map(|x| x.unwrap())
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     Ok(reading_list)
 }
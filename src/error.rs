@@ -0,0 +1,212 @@
+use std::fmt;
+
+/// The kinds of failures of vocabulist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// the config file or a setting in it is invalid
+    Config,
+    /// the database could not be opened or queried
+    Database,
+    /// the dictionary could not be opened or queried
+    Dictionary,
+    /// the tokenizer backend could not be run
+    Tokenizer,
+    /// AnkiConnect could not be reached or returned an unexpected response
+    AnkiConnect,
+    /// a file could not be read or written
+    Io,
+    /// an invalid argument or any other failure
+    Other,
+}
+
+impl Kind {
+    /// The exit code of the command line for the kind.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Kind::Config => 2,
+            Kind::Database => 3,
+            Kind::Dictionary => 4,
+            Kind::Tokenizer => 5,
+            Kind::AnkiConnect => 6,
+            Kind::Io => 7,
+            Kind::Other => 1,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Kind::Config => "Config error",
+            Kind::Database => "Database error",
+            Kind::Dictionary => "Dictionary error",
+            Kind::Tokenizer => "Tokenizer error",
+            Kind::AnkiConnect => "AnkiConnect error",
+            Kind::Io => "IO error",
+            Kind::Other => "Error",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A failure of vocabulist, with its kind, a message that says what failed and the error that caused it.
+#[derive(Debug)]
+pub struct Error {
+    kind: Kind,
+    message: Option<String>,
+    source: Option<Box<dyn std::error::Error + 'static>>,
+}
+
+impl Error {
+    pub fn new(kind: Kind, message: impl Into<String>) -> Error {
+        Error {
+            kind,
+            message: Some(message.into()),
+            source: None,
+        }
+    }
+
+    /// An error of the kind that only consists of the error that caused it.
+    fn wrap(kind: Kind, source: Box<dyn std::error::Error + 'static>) -> Error {
+        Error {
+            kind,
+            message: None,
+            source: Some(source),
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The exit code of the command line for the error.
+    pub fn exit_code(&self) -> i32 {
+        self.kind.exit_code()
+    }
+
+    /// The message with the messages of every error that caused it, as the command line prints it.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            report.push_str(&format!(": {}", error));
+            source = error.source();
+        }
+
+        report
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.kind, &self.message) {
+            (Kind::Other, Some(message)) => write!(f, "{}", message),
+            (_, Some(message)) => write!(f, "{}: {}", self.kind, message),
+            (_, None) => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref()
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Error {
+        Error::wrap(Kind::Database, Box::new(error))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::wrap(Kind::Io, Box::new(error))
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(error: toml::de::Error) -> Error {
+        Error::wrap(Kind::Config, Box::new(error))
+    }
+}
+
+/// Classify the errors of the functions that return any error by their type, everything else is Other.
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(error: Box<dyn std::error::Error>) -> Error {
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+
+        let kind = if error.is::<rusqlite::Error>() {
+            Kind::Database
+        } else if error.is::<std::io::Error>() {
+            Kind::Io
+        } else if error.is::<toml::de::Error>() {
+            Kind::Config
+        } else {
+            // a plain message has nothing to keep as the source
+            return Error::new(Kind::Other, error.to_string());
+        };
+
+        Error::wrap(kind, error)
+    }
+}
+
+/// Turn the error of a result into an Error of a kind, with a message that says what failed.
+pub trait Context<T> {
+    fn context(self, kind: Kind, message: &str) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Box<dyn std::error::Error>>> Context<T> for Result<T, E> {
+    fn context(self, kind: Kind, message: &str) -> Result<T, Error> {
+        self.map_err(|error| Error {
+            kind,
+            message: Some(message.to_string()),
+            source: Some(error.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_exit_code() {
+        let result: Result<(), std::io::Error> = Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No such file or directory",
+        ));
+        let error = result
+            .context(Kind::Io, "Failed to read book.txt")
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "IO error: Failed to read book.txt");
+        assert_eq!(
+            error.source().map(|x| x.to_string()),
+            Some("No such file or directory".to_string())
+        );
+        assert_eq!(
+            error.report(),
+            "IO error: Failed to read book.txt: No such file or directory"
+        );
+
+        let error_list: Vec<Box<dyn std::error::Error>> = vec![
+            Box::new(error),
+            Box::new(Error::new(Kind::AnkiConnect, "Failed to connect")),
+            Box::new(rusqlite::Error::InvalidQuery),
+            "Unknown order: random".into(),
+        ];
+        let code_list: Vec<i32> = error_list
+            .into_iter()
+            .map(|x| Error::from(x).exit_code())
+            .collect();
+
+        assert_eq!(code_list, vec![7, 6, 3, 1]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::config::ExclusionRule;
+use crate::error::{self, Context};
+use regex::Regex;
+use std::error::Error;
+
+/// A class of characters that a whole expression is written in.
+#[derive(Debug, PartialEq)]
+pub enum Script {
+    Hiragana,
+    Katakana,
+    Kana,
+    Ascii,
+    Numeric,
+}
+
+impl Script {
+    pub fn parse(string: &str) -> Result<Script, Box<dyn Error>> {
+        match string {
+            "hiragana" => Ok(Script::Hiragana),
+            "katakana" => Ok(Script::Katakana),
+            "kana" => Ok(Script::Kana),
+            "ascii" => Ok(Script::Ascii),
+            "numeric" => Ok(Script::Numeric),
+            _ => Err(
+                error::Error::new(error::Kind::Config, format!("Unknown script: {}", string))
+                    .into(),
+            ),
+        }
+    }
+
+    fn is_hiragana(c: char) -> bool {
+        ('\u{3041}'..='\u{309F}').contains(&c) || c == 'ー'
+    }
+
+    fn is_katakana(c: char) -> bool {
+        ('\u{30A0}'..='\u{30FF}').contains(&c)
+            || ('\u{31F0}'..='\u{31FF}').contains(&c)
+            || ('\u{FF66}'..='\u{FF9F}').contains(&c)
+    }
+
+    fn is_numeric(c: char) -> bool {
+        c.is_ascii_digit()
+            || ('０'..='９').contains(&c)
+            || "〇一二三四五六七八九十百千万億兆,.，．".contains(c)
+    }
+
+    /// Whether every character of string is in the script.
+    pub fn is_match(&self, string: &str) -> bool {
+        let is_char_match = |c: char| match self {
+            Script::Hiragana => Script::is_hiragana(c),
+            Script::Katakana => Script::is_katakana(c),
+            Script::Kana => Script::is_hiragana(c) || Script::is_katakana(c),
+            Script::Ascii => c.is_ascii(),
+            Script::Numeric => Script::is_numeric(c),
+        };
+
+        !string.is_empty() && string.chars().all(is_char_match)
+    }
+}
+
+/// An expression that a rule is evaluated against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub expression: String,
+    pub pos_list: Vec<String>,
+    pub frequency: i64,
+}
+
+/// A compiled exclusion rule, an expression matches if it matches every condition of the rule.
+#[derive(Debug)]
+pub struct Rule {
+    name: String,
+    regex: Option<Regex>,
+    script: Option<Script>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pos_list: Vec<String>,
+    min_frequency: Option<i64>,
+    max_frequency: Option<i64>,
+}
+
+impl Rule {
+    /// Compile a rule from the config, rules without a name are named after their position.
+    pub fn compile(rule: &ExclusionRule, index: usize) -> Result<Rule, Box<dyn Error>> {
+        let name = match rule.name() {
+            Some(name) => name.to_string(),
+            None => format!("rule {}", index + 1),
+        };
+
+        let regex = match rule.regex() {
+            Some(regex) => Some(
+                Regex::new(regex)
+                    .context(error::Kind::Config, &format!("Invalid regex in {}", name))?,
+            ),
+            None => None,
+        };
+
+        let script = match rule.script() {
+            Some(script) => Some(Script::parse(script)?),
+            None => None,
+        };
+
+        let rule = Rule {
+            name,
+            regex,
+            script,
+            min_length: rule.min_length(),
+            max_length: rule.max_length(),
+            pos_list: rule.pos().to_vec(),
+            min_frequency: rule.min_frequency(),
+            max_frequency: rule.max_frequency(),
+        };
+
+        if rule.regex.is_none()
+            && rule.script.is_none()
+            && rule.min_length.is_none()
+            && rule.max_length.is_none()
+            && rule.pos_list.is_empty()
+            && rule.min_frequency.is_none()
+            && rule.max_frequency.is_none()
+        {
+            return Err(error::Error::new(
+                error::Kind::Config,
+                format!("The exclusion rule {} has no conditions.", rule.name),
+            )
+            .into());
+        }
+
+        Ok(rule)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_match(&self, candidate: &Candidate) -> bool {
+        let expression = &candidate.expression;
+        let length = expression.chars().count();
+
+        self.regex.as_ref().is_none_or(|x| x.is_match(expression))
+            && self.script.as_ref().is_none_or(|x| x.is_match(expression))
+            && self.min_length.is_none_or(|x| length >= x)
+            && self.max_length.is_none_or(|x| length <= x)
+            && (self.pos_list.is_empty()
+                || candidate.pos_list.iter().any(|x| self.pos_list.contains(x)))
+            && self.min_frequency.is_none_or(|x| candidate.frequency >= x)
+            && self.max_frequency.is_none_or(|x| candidate.frequency <= x)
+    }
+}
+
+/// Compile the rules from the config.
+pub fn compile_rule_list(rule_list: &[ExclusionRule]) -> Result<Vec<Rule>, error::Error> {
+    Ok(rule_list
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| Rule::compile(rule, i))
+        .collect::<Result<Vec<Rule>, Box<dyn Error>>>()?)
+}
+
+/// Match the candidates against the rules, each matching candidate is paired with the first rule it matches.
+pub fn match_list(rule_list: &[Rule], candidate_list: Vec<Candidate>) -> Vec<(Candidate, &Rule)> {
+    candidate_list
+        .into_iter()
+        .filter_map(|candidate| {
+            rule_list
+                .iter()
+                .find(|rule| rule.is_match(&candidate))
+                .map(|rule| (candidate, rule))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(toml: &str) -> ExclusionRule {
+        toml::from_str(toml).expect("Failed to parse rule")
+    }
+
+    fn candidate(expression: &str, pos: &str, frequency: i64) -> Candidate {
+        Candidate {
+            expression: expression.to_string(),
+            pos_list: vec![pos.to_string()],
+            frequency,
+        }
+    }
+
+    #[test]
+    fn test_script() {
+        assert!(Script::Hiragana.is_match("ええ"));
+        assert!(!Script::Hiragana.is_match("エー"));
+        assert!(Script::Katakana.is_match("ドキドキ"));
+        assert!(Script::Kana.is_match("すごーい"));
+        assert!(Script::Ascii.is_match("OK"));
+        assert!(Script::Numeric.is_match("２０２０"));
+        assert!(!Script::Numeric.is_match("二人"));
+        assert!(!Script::Ascii.is_match(""));
+    }
+
+    #[test]
+    fn test_match_list() {
+        let rule_list = compile_rule_list(&[
+            rule("script = \"hiragana\"\nmax_length = 1"),
+            rule("name = \"names\"\npos = [\"名詞\"]\nregex = \"^[ァ-ヴー]+$\"\nmax_frequency = 2"),
+        ])
+        .expect("Failed to compile rules");
+
+        let candidate_list = vec![
+            candidate("ね", "助詞", 10),
+            candidate("ねこ", "名詞", 10),
+            candidate("アリス", "名詞", 2),
+            candidate("アリス", "名詞", 3),
+        ];
+
+        let match_list: Vec<(String, String)> = match_list(&rule_list, candidate_list)
+            .into_iter()
+            .map(|(candidate, rule)| (candidate.expression, rule.name().to_string()))
+            .collect();
+
+        assert_eq!(
+            match_list,
+            vec![
+                ("ね".to_string(), "rule 1".to_string()),
+                ("アリス".to_string(), "names".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_empty_rule() {
+        assert!(Rule::compile(&ExclusionRule::default(), 0).is_err());
+
+        // an invalid rule is an error in the config
+        let error = compile_rule_list(&[rule("regex = \"(\"")]).unwrap_err();
+        assert_eq!(error.kind(), error::Kind::Config);
+        assert!(compile_rule_list(&[rule("script = \"cyrillic\"")]).is_err());
+    }
+}
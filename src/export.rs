@@ -0,0 +1,160 @@
+use crate::error;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+/// The separator for lists in a single CSV or TSV field.
+const LIST_SEPARATOR: &str = ";";
+
+/// The file formats that can be exported.
+#[derive(Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl Format {
+    pub fn parse(string: &str) -> Result<Format, Box<dyn Error>> {
+        match &string.to_lowercase()[..] {
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            _ => Err(error::Error::new(
+                error::Kind::Other,
+                format!("Unknown export format: {}", string),
+            )
+            .into()),
+        }
+    }
+}
+
+/// An expression with everything that is known about it.
+#[derive(Debug, Serialize)]
+pub struct ExportRecord {
+    pub expression: String,
+    pub frequency: i64,
+    pub rank: Option<i64>,
+    pub pos: Vec<String>,
+    pub surface_strings: Vec<String>,
+    pub is_excluded: bool,
+    pub in_anki: bool,
+    pub is_learned: bool,
+    pub tags: Vec<String>,
+    pub sentences: Vec<String>,
+}
+
+impl ExportRecord {
+    fn header() -> Vec<&'static str> {
+        vec![
+            "expression",
+            "frequency",
+            "rank",
+            "pos",
+            "surface_strings",
+            "is_excluded",
+            "in_anki",
+            "is_learned",
+            "tags",
+            "sentences",
+        ]
+    }
+
+    fn field_list(&self) -> Vec<String> {
+        vec![
+            self.expression.clone(),
+            self.frequency.to_string(),
+            self.rank.map_or(String::new(), |x| x.to_string()),
+            self.pos.join(LIST_SEPARATOR),
+            self.surface_strings.join(LIST_SEPARATOR),
+            self.is_excluded.to_string(),
+            self.in_anki.to_string(),
+            self.is_learned.to_string(),
+            self.tags.join(LIST_SEPARATOR),
+            self.sentences.join(LIST_SEPARATOR),
+        ]
+    }
+}
+
+fn write_delimited(
+    writer: impl Write,
+    delimiter: u8,
+    record_list: &[ExportRecord],
+) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(writer);
+
+    csv_writer.write_record(ExportRecord::header())?;
+    for record in record_list.iter() {
+        csv_writer.write_record(record.field_list())?;
+    }
+
+    csv_writer.flush()?;
+
+    Ok(())
+}
+
+/// Write the records to writer in format.
+///
+/// Lists such as the pos, tags and sentences are joined with `;` in CSV and TSV, and are arrays in JSON.
+pub fn write(
+    writer: impl Write,
+    format: &Format,
+    record_list: &[ExportRecord],
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Csv => write_delimited(writer, b',', record_list),
+        Format::Tsv => write_delimited(writer, b'\t', record_list),
+        Format::Json => {
+            let mut writer = writer;
+            serde_json::to_writer_pretty(&mut writer, record_list)?;
+            writeln!(writer)?;
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_list() -> Vec<ExportRecord> {
+        vec![ExportRecord {
+            expression: "行く".to_string(),
+            frequency: 2,
+            rank: Some(120),
+            pos: vec!["動詞".to_string()],
+            surface_strings: vec!["行っ".to_string(), "行く".to_string()],
+            is_excluded: false,
+            in_anki: true,
+            is_learned: false,
+            tags: vec!["旅行".to_string()],
+            sentences: vec!["東京に行った".to_string(), "京都へ行く, 明日".to_string()],
+        }]
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut output: Vec<u8> = Vec::new();
+        write(&mut output, &Format::Csv, &record_list()).expect("Failed to write csv");
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "expression,frequency,rank,pos,surface_strings,is_excluded,in_anki,is_learned,tags,sentences\n行く,2,120,動詞,行っ;行く,false,true,false,旅行,\"東京に行った;京都へ行く, 明日\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let mut output: Vec<u8> = Vec::new();
+        write(&mut output, &Format::Json, &record_list()).expect("Failed to write json");
+
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(value[0]["expression"], "行く");
+        assert_eq!(value[0]["surface_strings"][1], "行く");
+        assert_eq!(value[0]["in_anki"], true);
+    }
+}
@@ -4,6 +4,7 @@ pub struct Expression {
     pos: Vec<String>,
     sentence: Vec<String>,
     surface_string: Vec<String>,
+    position: usize,
 
     reading: Vec<String>,
     definition: Vec<String>,
@@ -23,6 +24,7 @@ impl Expression {
             pos: pos,
             sentence: self.sentence,
             surface_string: self.surface_string,
+            position: self.position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -34,6 +36,7 @@ impl Expression {
             pos: self.pos,
             sentence: sentence,
             surface_string: self.surface_string,
+            position: self.position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -45,6 +48,19 @@ impl Expression {
             pos: self.pos,
             sentence: self.sentence,
             surface_string: surface_string,
+            position: self.position,
+            reading: self.reading,
+            definition: self.definition,
+        }
+    }
+
+    pub fn position(self, position: usize) -> Expression {
+        Expression {
+            expression: self.expression,
+            pos: self.pos,
+            sentence: self.sentence,
+            surface_string: self.surface_string,
+            position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -65,4 +81,8 @@ impl Expression {
     pub fn get_pos(&self) -> &Vec<String> {
         &self.pos
     }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
 }
@@ -0,0 +1,259 @@
+use crate::error;
+use crate::word_list::Column;
+use serde_json::Value;
+use std::error::Error;
+use std::io::{Read, Seek};
+
+/// The rank of a word in an external frequency list, 1 is the most frequent word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyEntry {
+    pub expression: String,
+    /// the reading of the word, empty if the list has no readings
+    pub reading: String,
+    pub rank: i64,
+}
+
+/// Read a frequency list from a delimited file.
+///
+/// Without a rank column the words are ranked in the order of the file.
+pub fn read_delimited(
+    reader: impl Read,
+    column: &Column,
+    reading_column: Option<&Column>,
+    rank_column: Option<&Column>,
+    delimiter: u8,
+    has_header: bool,
+) -> Result<Vec<FrequencyEntry>, Box<dyn Error>> {
+    let has_header = has_header
+        || [Some(column), reading_column, rank_column]
+            .iter()
+            .any(|x| matches!(x, Some(Column::Name(_))));
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(reader);
+
+    let header = csv_reader.headers()?.clone();
+    let index = column.index(&header)?;
+    let reading_index = match reading_column {
+        Some(column) => Some(column.index(&header)?),
+        None => None,
+    };
+    let rank_index = match rank_column {
+        Some(column) => Some(column.index(&header)?),
+        None => None,
+    };
+
+    let mut entry_list: Vec<FrequencyEntry> = Vec::new();
+    for (i, record) in csv_reader.records().enumerate() {
+        let record = record?;
+
+        let expression = match record.get(index) {
+            Some(expression) if !expression.trim().is_empty() => expression.trim().to_string(),
+            _ => continue,
+        };
+
+        let reading = reading_index
+            .and_then(|x| record.get(x))
+            .map_or(String::new(), |x| x.trim().to_string());
+
+        let rank = match rank_index {
+            Some(rank_index) => match record.get(rank_index).map(|x| x.trim().parse::<i64>()) {
+                Some(Ok(rank)) => rank,
+                _ => {
+                    return Err(error::Error::new(
+                        error::Kind::Io,
+                        format!("Invalid rank for {} on row {}", expression, i + 1),
+                    )
+                    .into())
+                }
+            },
+            None => entry_list.len() as i64 + 1,
+        };
+
+        entry_list.push(FrequencyEntry {
+            expression,
+            reading,
+            rank,
+        });
+    }
+
+    Ok(entry_list)
+}
+
+/// Parse a frequency value, which is a number, a string starting with a number, or an object with a value.
+fn parse_frequency(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|x| x.round() as i64),
+        Value::String(string) => string
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<i64>()
+            .ok(),
+        Value::Object(object) => match object.get("value") {
+            Some(value) => parse_frequency(value),
+            None => object.get("displayValue").and_then(parse_frequency),
+        },
+        _ => None,
+    }
+}
+
+/// Parse a term meta bank of a Yomitan dictionary, entries other than frequencies are skipped.
+///
+/// The frequency is returned as the rank for lists that are rank based.
+fn parse_term_meta_bank(content: &str) -> Result<Vec<FrequencyEntry>, Box<dyn Error>> {
+    let bank: Vec<Vec<Value>> = serde_json::from_str(content)?;
+
+    let mut entry_list: Vec<FrequencyEntry> = Vec::new();
+    for row in bank {
+        let (expression, mode, data) = match &row[..] {
+            [Value::String(expression), Value::String(mode), data, ..] => (expression, mode, data),
+            _ => continue,
+        };
+
+        if mode != "freq" {
+            continue;
+        }
+
+        let (reading, frequency) = match data.get("frequency") {
+            Some(frequency) => (
+                data.get("reading").and_then(|x| x.as_str()).unwrap_or(""),
+                frequency,
+            ),
+            None => ("", data),
+        };
+
+        if let Some(rank) = parse_frequency(frequency) {
+            entry_list.push(FrequencyEntry {
+                expression: expression.to_string(),
+                reading: reading.to_string(),
+                rank,
+            });
+        }
+    }
+
+    Ok(entry_list)
+}
+
+/// Read the frequencies of a Yomitan frequency dictionary zip.
+///
+/// Returns the title of the dictionary and its entries,
+/// lists that count occurrences are ranked from the most frequent word.
+pub fn read_yomitan(
+    reader: impl Read + Seek,
+) -> Result<(Option<String>, Vec<FrequencyEntry>), Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+
+    let mut index = String::new();
+    archive
+        .by_name("index.json")
+        .map_err(|_| "The zip is not a Yomitan dictionary, index.json is missing")?
+        .read_to_string(&mut index)?;
+    let index: Value = serde_json::from_str(&index)?;
+
+    let title = index
+        .get("title")
+        .and_then(|x| x.as_str())
+        .map(|x| x.to_string());
+    let is_occurrence_based =
+        index.get("frequencyMode").and_then(|x| x.as_str()) == Some("occurrence-based");
+
+    let mut entry_list: Vec<FrequencyEntry> = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !(file.name().starts_with("term_meta_bank_") && file.name().ends_with(".json")) {
+            continue;
+        }
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        entry_list.extend(parse_term_meta_bank(&content)?);
+    }
+
+    if is_occurrence_based {
+        entry_list.sort_by_key(|x| std::cmp::Reverse(x.rank));
+        for (i, entry) in entry_list.iter_mut().enumerate() {
+            entry.rank = i as i64 + 1;
+        }
+    }
+
+    Ok((title, entry_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(expression: &str, reading: &str, rank: i64) -> FrequencyEntry {
+        FrequencyEntry {
+            expression: expression.to_string(),
+            reading: reading.to_string(),
+            rank,
+        }
+    }
+
+    #[test]
+    fn test_read_delimited() {
+        let content = "猫\tねこ\n\t\n犬\tいぬ\n";
+
+        let entry_list = read_delimited(
+            content.as_bytes(),
+            &Column::Index(1),
+            Some(&Column::Index(2)),
+            None,
+            b'\t',
+            false,
+        )
+        .expect("Failed to read frequency list");
+
+        assert_eq!(
+            entry_list,
+            vec![entry("猫", "ねこ", 1), entry("犬", "いぬ", 2)]
+        );
+    }
+
+    #[test]
+    fn test_read_delimited_rank_column() {
+        let content = "rank,word\n10,猫\nmany,犬\n";
+
+        let column = Column::parse("word").unwrap();
+        let rank_column = Column::parse("rank").unwrap();
+
+        let result = read_delimited(
+            content.as_bytes(),
+            &column,
+            None,
+            Some(&rank_column),
+            b',',
+            false,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_term_meta_bank() {
+        let content = r#"[
+            ["猫", "freq", 120],
+            ["犬", "freq", {"value": 250, "displayValue": "250㋕"}],
+            ["日本", "freq", {"reading": "にほん", "frequency": {"value": 300}}],
+            ["日本", "freq", {"reading": "にっぽん", "frequency": "900"}],
+            ["鳥", "pitch", {"reading": "とり", "pitches": []}]
+        ]"#;
+
+        let entry_list = parse_term_meta_bank(content).expect("Failed to parse bank");
+
+        assert_eq!(
+            entry_list,
+            vec![
+                entry("猫", "", 120),
+                entry("犬", "", 250),
+                entry("日本", "にほん", 300),
+                entry("日本", "にっぽん", 900),
+            ]
+        );
+    }
+}
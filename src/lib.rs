@@ -1,25 +1,41 @@
 mod anki;
 pub mod config;
 mod database;
+pub mod date;
 mod dictionary;
+pub mod error;
+mod exclusion;
+pub mod export;
 mod expression;
+mod frequency_list;
+mod plan;
 mod posconverter;
-mod progress_bar;
+mod scoring;
 mod tokenizer;
+mod vocabulist;
+pub mod word_list;
 
-use clap::ArgMatches;
 use config::Config;
+pub use database::filter::Filter;
+pub use database::history::{Change, HistoryEntry, UndoSummary};
+pub use database::merge::{MergePolicy, MergeSummary};
+pub use database::stats::{
+    Coverage, Difficulty, FirstOccurrence, Keyword, NamedCoverage, Stats, Target,
+};
+pub use database::{ExpressionRecord, MinedExpression};
 use expression::Expression;
 use itertools::Itertools;
+pub use plan::{Chunk, Split};
 use rusqlite::Connection;
+pub use scoring::Score;
 use std::error::Error;
-use std::fs;
-use std::path::{Path, PathBuf};
-use tokenizer::jumanpp::Jumanpp;
-use tokenizer::mecab::Mecab;
+use std::path::PathBuf;
+pub use tokenizer::cache::CacheStats;
 use tokenizer::token::Token;
-use tokenizer::tokenize::Tokenize;
-use tokenizer::Tokenizer;
+pub use vocabulist::{
+    Card, FrequencyListOptions, ImportOptions, ImportSummary, Order, Progress, Recommendation,
+    Search, SyncSummary, Vocabulist,
+};
 
 // pub struct Preference {
 //     pub database_path: String,
@@ -29,10 +45,9 @@ use tokenizer::Tokenizer;
 
 pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-/// Open a file and clean the contents
-fn open_file(path: &str) -> Vec<String> {
-    let contents = fs::read_to_string(path)
-        .expect("Can't read file")
+/// Clean a text and split it into sentences.
+fn sentence_list(contents: &str) -> Vec<String> {
+    let contents = contents
         .replace(&['「', '」', '『', '』', '…'][..], "")
         .replace(&['。'][..], "。\n")
         .replace(&['？'][..], "？\n")
@@ -53,7 +68,8 @@ fn token_list_to_expression_list(token_list: Vec<Token>) -> Vec<Expression> {
         let expression = Expression::new(token.get_token().to_string())
             .pos(token.get_pos().clone())
             .sentence(token.get_sentence().clone())
-            .surface_string(token.get_surface_string().clone());
+            .surface_string(token.get_surface_string().clone())
+            .position(token.get_position());
 
         expression_list.push(expression);
     }
@@ -61,10 +77,6 @@ fn token_list_to_expression_list(token_list: Vec<Token>) -> Vec<Expression> {
     expression_list
 }
 
-fn database_connection(database_path: &PathBuf) -> Connection {
-    database::connect(database_path)
-}
-
 fn format_anki_definition(
     definition_list: &Vec<Vec<String>>,
     is_specific_definition: bool,
@@ -112,14 +124,17 @@ fn format_anki_sentence(sentence_list: &Vec<String>) -> String {
     sentence_list[0].to_string()
 }
 
+/// Create flashcards for the expressions until max flashcards have been created.
+///
+/// Returns the number of flashcards that were created.
 fn create_flashcards_from_expression_list(
-    p: Config,
+    p: &Config,
     conn: &mut Connection,
     dict: &Connection,
     expression_list: Vec<Expression>,
     max: i32,
     callback: &dyn Fn(),
-) -> Result<(), Box<dyn Error>> {
+) -> Result<usize, Box<dyn Error>> {
     let mut i = 0;
     for expression in expression_list.iter() {
         let expression_string = &expression.get_expression();
@@ -128,7 +143,11 @@ fn create_flashcards_from_expression_list(
             dictionary::select_definition_for_expression(&dict, expression_string)?;
         let pos_list = database::select_pos_for_expression(&conn, expression_string)?;
         let reading_list = dictionary::select_reading_for_expression(&dict, expression_string)?;
-        let sentence_list = database::select_sentence_for_expression(&conn, expression_string)?;
+        // use the sentence chosen for the expression, if there is one
+        let sentence_list = match expression.get_sentence().is_empty() {
+            true => database::select_sentence_for_expression(conn, expression_string)?,
+            false => expression.get_sentence().clone(),
+        };
 
         if definition_list.len() == 0 {
             database::update_is_excluded_for_expression_list(
@@ -154,14 +173,16 @@ fn create_flashcards_from_expression_list(
         let reading_string = format_anki_reading(&reading_list);
         let sentence_string = format_anki_sentence(&sentence_list);
         let url_list = anki::create_url_list(expression_string, &reading_list);
+        let tag_list = database::select_tag_for_expression(conn, expression_string)?;
 
         anki::insert_note(
-            &p,
+            p,
             &definition_string,
             &expression_string,
             &reading_string,
             &sentence_string,
             &url_list,
+            &tag_list,
         )?;
         database::update_in_anki_for_expression(conn, 1u32, expression_string)?;
 
@@ -174,320 +195,37 @@ fn create_flashcards_from_expression_list(
         callback();
     }
 
-    Ok(())
-}
-
-pub fn import(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let database_path = p.database_path();
-    let mut conn = database::connect(database_path);
-
-    let backend_string = p.backend();
-    let path = Path::new(m.value_of("path").unwrap());
-
-    fn import_file(conn: &mut Connection, path: &str, backend: &str) -> Result<(), Box<dyn Error>> {
-        let sentence_list = open_file(path);
-
-        let len = sentence_list.len() as u64;
-        let pb = progress_bar::new(len, "Tokenizing");
-        let mut callback = || pb.inc(1);
-
-        // get the tokenizer backend
-        let backend: Box<dyn Tokenize> = match backend {
-            "jumanpp" => Box::new(Jumanpp::new(PathBuf::from("jumanpp"))),
-            _ => Box::new(Mecab::new(PathBuf::from("mecab"))),
-        };
-
-        let tokenizer = Tokenizer::new(backend);
-
-        let expression_list =
-            token_list_to_expression_list(tokenizer.tokenize(&sentence_list, &mut callback)?);
-        pb.finish_with_message("Tokenized");
-
-        let duplicate_sentence_list = database::select_imported_sentence_list(conn, &sentence_list)
-            .expect("Failed to retrieve sentences from the database");
-        let expression_list =
-            database::filter_imported_expression_list(&duplicate_sentence_list, expression_list);
-
-        let len = expression_list.len() as u64;
-        let pb = progress_bar::new(len, "Importing");
-        database::insert_expression_list(conn, expression_list, &|| pb.inc(1))
-            .expect("Failed to insert expression");
-
-        pb.finish_with_message("Imported");
-
-        Ok(())
-    }
-
-    if path.is_dir() {
-        // Parse each file in the directory
-        for path in fs::read_dir(path).expect("Could not get file list") {
-            if let Ok(file) = path {
-                println!("Importing {}", &file.path().to_str().unwrap());
-                import_file(&mut conn, &file.path().to_str().unwrap(), backend_string)?;
-                println!("");
-            }
-        }
-    } else {
-        if let Some(file) = path.to_str() {
-            println!("Importing {}", file);
-            import_file(&mut conn, file, backend_string)?;
-            println!("");
-        }
-    }
-
-    Ok(())
+    Ok(i as usize)
 }
 
-pub fn list(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let database_path = p.database_path();
-    let conn = database::connect(database_path);
-
-    match m.is_present("pos") {
-        true => {
-            let is_excluded = m.is_present("excluded");
-            let is_asc = m.is_present("asc");
-            let limit = m.value_of("number").unwrap().parse::<i32>().unwrap();
-
-            let pos_list = database::select_pos_list(&conn, is_excluded, is_asc, limit)?;
-
-            for pos in pos_list {
-                println!("{}", pos);
-            }
-        }
-        false => {
-            let in_anki = m.is_present("anki");
-            let is_excluded = m.is_present("excluded");
-            let is_learned = m.is_present("learned");
-            let order_by = match m.value_of("order") {
-                Some(order) => order,
-                None => "frequency",
-            };
-            let is_asc = m.is_present("asc");
-            let limit = m.value_of("number").unwrap().parse::<i32>().unwrap();
-
-            let expression_list = database::select_expression_list(
-                &conn,
-                in_anki,
-                is_excluded,
-                is_learned,
-                order_by,
-                is_asc,
-                limit,
-            )
-            .expect("Failed to get expressions from database");
-
-            for expression in expression_list {
-                println!("{}", expression.get_expression());
-            }
-        }
-    }
-
-    Ok(())
-}
-
-pub fn exclude(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let database_path = p.database_path();
-    let mut conn = database::connect(database_path);
-
-    if let Some(path) = m.value_of("path") {
-        let file_content = fs::read_to_string(path).expect("Failed to open file");
-        let line_list = file_content.split_whitespace();
-
-        match m.is_present("pos") {
-            true => {
-                let pos_list: Vec<String> = line_list.map(|x| x.to_string()).collect();
-                let len: u64 = pos_list.len() as u64;
-                let pb = progress_bar::new(len, "Excluding");
-                crate::database::update_is_excluded_for_pos_list(
-                    &mut conn,
-                    &pos_list,
-                    true,
-                    &|| pb.inc(1),
-                )?;
-                pb.finish_with_message("Excluded");
-            }
-            false => {
-                let expression_list: Vec<Expression> =
-                    line_list.map(|x| Expression::new(x.to_string())).collect();
-                let len: u64 = expression_list.len() as u64;
-                let pb = progress_bar::new(len, "Excluding");
-                crate::database::update_is_excluded_for_expression_list(
-                    &mut conn,
-                    &expression_list,
-                    true,
-                    &|| pb.inc(1),
-                )?;
-                pb.finish_with_message("Excluded");
-            }
-        }
-    }
-
-    Ok(())
-}
-
-pub fn include(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let database_path = p.database_path();
-    let mut conn = database::connect(database_path);
-
-    if let Some(path) = m.value_of("path") {
-        let file_content = fs::read_to_string(path).expect("Failed to open file");
-        let line_list = file_content.split_whitespace();
-
-        match m.is_present("pos") {
-            true => {
-                let pos_list: Vec<String> = line_list.map(|x| x.to_string()).collect();
-                let len: u64 = pos_list.len() as u64;
-                let pb = progress_bar::new(len, "Including");
-                crate::database::update_is_excluded_for_pos_list(
-                    &mut conn,
-                    &pos_list,
-                    false,
-                    &|| pb.inc(1),
-                )?;
-                pb.finish_with_message("Included");
-            }
-            false => {
-                let expression_list: Vec<Expression> =
-                    line_list.map(|x| Expression::new(x.to_string())).collect();
-                let len: u64 = expression_list.len() as u64;
-                let pb = progress_bar::new(len, "Including");
-                crate::database::update_is_excluded_for_expression_list(
-                    &mut conn,
-                    &expression_list,
-                    false,
-                    &|| pb.inc(1),
-                )?;
-                pb.finish_with_message("Included");
-            }
-        }
-    }
-
-    Ok(())
-}
-
-pub fn generate(p: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let database_path = p.database_path();
-    let mut conn = database::connect(database_path);
-
-    let dictionary_path = p.dictionary_path();
-    let dict = dictionary::connect(&dictionary_path)?;
-
-    if let Some(max) = m.value_of("number") {
-        let max = max.parse::<i32>().unwrap();
-        let limit = max * 2;
-
-        let expression_list = database::select_expression_list(
-            &conn,
-            false,
-            false,
-            false,
-            "frequency",
-            false,
-            limit,
-        )?;
-
-        let pb = progress_bar::new(max as u64, "Generating");
-
-        create_flashcards_from_expression_list(p, &mut conn, &dict, expression_list, max, &|| {
-            pb.inc(1)
-        })?;
-
-        pb.finish_with_message("Finished");
-    }
-
-    Ok(())
-}
-
-pub fn sync(p: Config, _: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // Initialize the database
-    let conn = database_connection(p.database_path());
-    let expression_list = anki::expression_list(&p)?;
-
-    database::reset_in_anki(&conn)?;
-
-    for expression in expression_list.iter() {
-        println!("{}", expression);
-        database::update_in_anki_for_expression(&conn, 1, expression)?;
-    }
+fn exclude_match_list(
+    conn: &mut Connection,
+    description: &str,
+    match_list: &[(exclusion::Candidate, &exclusion::Rule)],
+) -> Result<(), Box<dyn Error>> {
+    let expression_list: Vec<Expression> = match_list
+        .iter()
+        .map(|(candidate, _)| Expression::new(candidate.expression.to_string()))
+        .collect();
 
-    Ok(())
+    database::history::record(conn, description, |conn| {
+        database::update_is_excluded_for_expression_list(conn, &expression_list, true, &|| {})
+    })
 }
 
-pub fn config(_: Config, m: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let config_directory;
-    let config_file;
-    let home_path = dirs::home_dir().expect("Failed to get home directory");
+/// The directory that holds the config file of the default profile and the other profiles.
+pub fn config_root() -> Result<PathBuf, error::Error> {
+    let home_path = dirs::home_dir().ok_or_else(|| {
+        error::Error::new(
+            error::Kind::Config,
+            "Failed to find the home directory".to_string(),
+        )
+    })?;
 
     match !cfg!(debug_assertions) {
         // path for release
-        true => {
-            config_directory = home_path.join(".vocabulist_rs");
-            config_file = config_directory.join("config.toml");
-        }
+        true => Ok(home_path.join(".vocabulist_rs")),
         // path for dev
-        false => {
-            config_directory = home_path.join(".vocabulist_rs_dev");
-            config_file = config_directory.join("config.toml");
-        }
-    }
-
-    match config_file.is_file() {
-        true => {
-            match m.is_present("force") {
-                true => {
-                    // create the config file
-                    println!(
-                        "Creating new configuration file at {}",
-                        config_file.to_str().unwrap()
-                    );
-
-                    // create the directory
-                    if !config_directory.is_dir() {
-                        fs::create_dir_all(&config_directory)?;
-                    }
-
-                    let config = match m.is_present("homebrew") {
-                        true => Config::homebrew(config_directory),
-                        false => Config::default(config_directory),
-                    };
-
-                    let toml = toml::to_string(&config)?;
-                    fs::write(config_file, &toml)?;
-                }
-                false => {
-                    println!("Configuration file already exists");
-                    println!("Doing nothing");
-                    println!("");
-                    println!("If you would like to overwrite the file run `vocabulist_rs config --force`")
-                }
-            }
-        }
-        false => {
-            // create the config file
-            println!(
-                "Creating new configuration file at {}",
-                config_file.to_str().unwrap()
-            );
-
-            // create the directory
-            if !config_directory.is_dir() {
-                fs::create_dir_all(&config_directory)?;
-            }
-
-            let config = match m.is_present("homebrew") {
-                true => Config::homebrew(config_directory),
-                false => Config::default(config_directory),
-            };
-
-            let toml = toml::to_string(&config)?;
-            fs::write(config_file, &toml)?;
-        }
+        false => Ok(home_path.join(".vocabulist_rs_dev")),
     }
-
-    Ok(())
 }
@@ -1,11 +1,135 @@
-use clap::{App, Arg, SubCommand};
-use dirs;
+mod cli;
+mod concordance;
+mod progress_bar;
+
+use clap::{App, AppSettings, Arg, SubCommand};
 use std::error::Error;
 use std::fs;
+use vocabulist_rs::config::profile;
 use vocabulist_rs::config::Config;
+use vocabulist_rs::error::{self, Context};
 use vocabulist_rs::VERSION;
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// The filter query argument.
+fn query_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("query")
+        .short("q")
+        .long("query")
+        .value_name("QUERY")
+        .help("Filter expressions with a saved query from the config or a query like 'pos:動詞 freq>=5 -tag:names'")
+}
+
+/// The sort order argument.
+fn order_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("order")
+        .short("o")
+        .long("order")
+        .takes_value(true)
+        .possible_value("frequency")
+        .possible_value("expression")
+        .possible_value("id")
+        .possible_value("rank")
+        .possible_value("combined")
+        .possible_value("score")
+        .possible_value("recent")
+        .possible_value("range")
+        .possible_value("dispersion")
+        .help("Column to sort by, rank, combined (the mean of the rank and the frequency rank) and score (the scoring in the config) put the most frequent words first, recent puts the most recently seen words first, range sorts by the number of documents and dispersion by Juilland's D")
+}
+
+/// The argument selecting recently seen expressions.
+fn since_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("since")
+        .long("since")
+        .value_name("DATE")
+        .help("Only select expressions seen on or after the date (YYYY-MM-DD) or in the last days or weeks (7d, 2w)")
+}
+
+/// The arguments shared by the commands that select expressions.
+fn expression_filter_arg_list<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("number")
+            .value_name("NUM")
+            .default_value("-1")
+            .required(false)
+            .help("Number of entries to list"),
+        Arg::with_name("asc")
+            .short("a")
+            .long("asc")
+            .help("Sort by ascending instead of descending"),
+        Arg::with_name("anki")
+            .long("anki")
+            .help("Show expressions that are already in anki"),
+        Arg::with_name("learned")
+            .long("learn")
+            .help("Show expressions that have already been learned"),
+        Arg::with_name("excluded")
+            .long("exclude")
+            .help("Show expressions that have been excluded"),
+        Arg::with_name("tag")
+            .short("t")
+            .long("tag")
+            .value_name("TAG")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Only show expressions with this tag, repeat to require several tags"),
+        query_arg(),
+        order_arg(),
+        since_arg(),
+    ]
+}
+
+/// The words given on the command line, followed by the arguments that read them from a file instead.
+fn word_arg_list<'a, 'b>(help: &'a str) -> Vec<Arg<'a, 'b>> {
+    let mut arg_list = vec![Arg::with_name("word")
+        .value_name("WORD")
+        .multiple(true)
+        .help(help)];
+    arg_list.extend(word_list_arg_list());
+
+    arg_list
+}
+
+/// The arguments shared by the commands that read a list of words.
+fn word_list_arg_list<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("path")
+            .short("f")
+            .long("file")
+            .value_name("PATH")
+            .takes_value(true)
+            .help("Path to file of words, use - to read from stdin (the default without WORD)"),
+        Arg::with_name("column")
+            .short("c")
+            .long("column")
+            .value_name("COLUMN")
+            .takes_value(true)
+            .help("Read a CSV/TSV file and take the words from this column (1-based index or header name)"),
+        Arg::with_name("delimiter")
+            .short("d")
+            .long("delimiter")
+            .value_name("CHAR")
+            .takes_value(true)
+            .default_value(",")
+            .help("Field delimiter for --column, use tab for TSV files"),
+        Arg::with_name("header")
+            .long("header")
+            .requires("column")
+            .help("Skip the first row of the file"),
+    ]
+}
+
+/// Print the error and exit with the code of its kind, see the guide for the codes.
+fn main() {
+    if let Err(error) = run() {
+        let error = error::Error::from(error);
+
+        eprintln!("ERROR: {}", error.report());
+        std::process::exit(error.exit_code());
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
     let match_list = App::new("Vocabulist")
         .version(VERSION)
         .author("Odaka Ui <odakaui@example.com>")
@@ -18,13 +142,33 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .help("Sets a custom config file")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("NAME")
+                .global(true)
+                .help("Use this profile instead of the one set with `profile switch` or VOCABULIST_PROFILE"),
+        )
         .subcommand(
-            SubCommand::with_name("import").about("import file(s)").arg(
-                Arg::with_name("path")
-                    .value_name("PATH")
-                    .required(true)
-                    .help("Import file/directory to database"),
-            ),
+            SubCommand::with_name("import")
+                .about("import file(s)")
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .required(true)
+                        .help("Import file/directory to database"),
+                )
+                .arg(
+                    Arg::with_name("no-cache")
+                        .long("no-cache")
+                        .help("Tokenize every sentence without consulting the cache"),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .value_name("DATE")
+                        .help("Date the document was read (YYYY-MM-DD), defaults to now"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("exclude")
@@ -32,13 +176,25 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .arg(
                     Arg::with_name("path")
                         .value_name("PATH")
-                        .required(true)
+                        .required_unless("rules")
                         .help("Path to file of words to exclude"),
                 )
                 .arg(
                     Arg::with_name("pos")
                         .long("pos")
                         .help("Exclude pos and all expressions associated with them"),
+                )
+                .arg(
+                    Arg::with_name("rules")
+                        .long("rules")
+                        .conflicts_with_all(&["path", "pos"])
+                        .help("Exclude the expressions matching the exclusion rules in the config"),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .requires("rules")
+                        .help("Print the matching expressions and rules without excluding them"),
                 ),
         )
         .subcommand(
@@ -56,6 +212,28 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .help("Exclude pos and all expressions associated with them"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("learn")
+                .about("mark expressions as learned")
+                .args(&word_arg_list("Words to mark as learned")),
+        )
+        .subcommand(
+            SubCommand::with_name("unlearn")
+                .about("mark expressions as not learned")
+                .args(&word_arg_list("Words to mark as not learned")),
+        )
+        .subcommand(
+            SubCommand::with_name("priority")
+                .about("set the manual priority that is part of the score of expressions")
+                .setting(AppSettings::AllowNegativeNumbers)
+                .arg(
+                    Arg::with_name("priority")
+                        .value_name("PRIORITY")
+                        .required(true)
+                        .help("Priority to set, higher is generated first, negative is generated last and 0 resets it"),
+                )
+                .args(&word_arg_list("Words to set the priority of")),
+        )
         .subcommand(
             SubCommand::with_name("generate")
                 .about("generate flashcards")
@@ -65,9 +243,397 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .required(false)
                         .default_value("10")
                         .help("Number of flashcards to generate"),
-                ),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .short("t")
+                        .long("tag")
+                        .value_name("TAG")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only generate flashcards for expressions with this tag"),
+                )
+                .arg(query_arg())
+                .arg(order_arg())
+                .arg(since_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("tag")
+                .about("organize expressions with tags")
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("add a tag to expressions")
+                        .arg(
+                            Arg::with_name("tag")
+                                .value_name("TAG")
+                                .required(true)
+                                .help("Tag to add"),
+                        )
+                        .args(&word_arg_list("Words to tag")),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("remove a tag from expressions")
+                        .arg(
+                            Arg::with_name("tag")
+                                .value_name("TAG")
+                                .required(true)
+                                .help("Tag to remove"),
+                        )
+                        .args(&word_arg_list("Words to untag")),
+                )
+                .subcommand(SubCommand::with_name("list").about("list tags and their number of expressions")),
+        )
+        .subcommand(
+            SubCommand::with_name("frequency")
+                .about("manage external frequency lists")
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("import a CSV/TSV word-rank list or a Yomitan frequency dictionary zip")
+                        .arg(
+                            Arg::with_name("path")
+                                .value_name("PATH")
+                                .required(true)
+                                .help("Path to the frequency list"),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .short("n")
+                                .long("name")
+                                .value_name("NAME")
+                                .takes_value(true)
+                                .help("Name of the list, defaults to the dictionary title or file name"),
+                        )
+                        .arg(
+                            Arg::with_name("column")
+                                .short("c")
+                                .long("column")
+                                .value_name("COLUMN")
+                                .default_value("1")
+                                .help("Column of the words (1-based index or header name)"),
+                        )
+                        .arg(
+                            Arg::with_name("reading-column")
+                                .long("reading-column")
+                                .value_name("COLUMN")
+                                .takes_value(true)
+                                .help("Column of the readings"),
+                        )
+                        .arg(
+                            Arg::with_name("rank-column")
+                                .long("rank-column")
+                                .value_name("COLUMN")
+                                .takes_value(true)
+                                .help("Column of the ranks, the words are ranked in file order without it"),
+                        )
+                        .arg(
+                            Arg::with_name("delimiter")
+                                .short("d")
+                                .long("delimiter")
+                                .value_name("CHAR")
+                                .takes_value(true)
+                                .help("Field delimiter, defaults to tab for .tsv and .txt files and , otherwise"),
+                        )
+                        .arg(
+                            Arg::with_name("header")
+                                .long("header")
+                                .help("Skip the first row of the file"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("remove a frequency list")
+                        .arg(
+                            Arg::with_name("name")
+                                .value_name("NAME")
+                                .required(true)
+                                .help("Name of the list to remove"),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").about("list frequency lists and their number of entries")),
         )
         .subcommand(SubCommand::with_name("sync").about("sync database with anki"))
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("search imported sentences")
+                .arg(
+                    Arg::with_name("query")
+                        .value_name("QUERY")
+                        .required(true)
+                        .help("Words to search for, separate the words of a phrase with spaces"),
+                )
+                .arg(
+                    Arg::with_name("lemma")
+                        .short("l")
+                        .long("lemma")
+                        .conflicts_with("substring")
+                        .help("Find every surface form of the expression QUERY"),
+                )
+                .arg(
+                    Arg::with_name("substring")
+                        .short("s")
+                        .long("substring")
+                        .help("Find QUERY anywhere in the raw sentence text"),
+                )
+                .arg(
+                    Arg::with_name("number")
+                        .short("n")
+                        .long("number")
+                        .value_name("NUM")
+                        .default_value("-1")
+                        .help("Number of sentences to search"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .short("w")
+                        .long("width")
+                        .value_name("COLUMNS")
+                        .default_value("30")
+                        .help("Columns of context on each side of the match"),
+                )
+                .arg(
+                    Arg::with_name("plain")
+                        .long("plain")
+                        .help("Mark the match with brackets instead of colors"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("recount")
+                .about("recompute expression frequencies from stored occurrences"),
+        )
+        .subcommand(
+            SubCommand::with_name("cache")
+                .about("manage the tokenization cache")
+                .subcommand(SubCommand::with_name("stats").about("show cache statistics"))
+                .subcommand(SubCommand::with_name("clear").about("remove every cached sentence")),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("show corpus statistics and known-word coverage")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the statistics as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("recommend")
+                .about("rank documents by how easy they are to read")
+                .arg(
+                    Arg::with_name("number")
+                        .short("n")
+                        .long("number")
+                        .value_name("NUM")
+                        .default_value("-1")
+                        .help("Number of documents to list"),
+                )
+                .arg(
+                    Arg::with_name("document")
+                        .short("d")
+                        .long("document")
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .help("List the fewest words to learn to reach the target coverage of this document"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .short("t")
+                        .long("target")
+                        .value_name("PERCENT")
+                        .default_value("95")
+                        .help("Coverage to reach with --document"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mine")
+                .about("find sentences with exactly one unknown word")
+                .arg(
+                    Arg::with_name("number")
+                        .short("n")
+                        .long("number")
+                        .value_name("NUM")
+                        .default_value("-1")
+                        .help("Number of words to list"),
+                )
+                .arg(
+                    Arg::with_name("sentences")
+                        .short("s")
+                        .long("sentences")
+                        .value_name("NUM")
+                        .default_value("3")
+                        .help("Number of sentences for each word"),
+                )
+                .arg(
+                    Arg::with_name("generate")
+                        .long("generate")
+                        .help("Generate a flashcard for each word with its first sentence"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("keywords")
+                .about("list the distinctive unknown words of a document")
+                .arg(
+                    Arg::with_name("document")
+                        .value_name("DOCUMENT")
+                        .required(true)
+                        .help("Name or path of the document"),
+                )
+                .arg(
+                    Arg::with_name("number")
+                        .short("n")
+                        .long("number")
+                        .value_name("NUM")
+                        .default_value("20")
+                        .help("Number of keywords to list, -1 for all"),
+                )
+                .arg(
+                    Arg::with_name("min-count")
+                        .short("m")
+                        .long("min-count")
+                        .value_name("NUM")
+                        .default_value("2")
+                        .help("Skip words that occur fewer times in the document"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .short("t")
+                        .long("tag")
+                        .value_name("TAG")
+                        .takes_value(true)
+                        .help("Add this tag to the keywords"),
+                )
+                .arg(
+                    Arg::with_name("generate")
+                        .long("generate")
+                        .help("Generate flashcards for the keywords"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("plan")
+                .about("list the unknown words of a document by the chapter or window they first appear in")
+                .arg(
+                    Arg::with_name("document")
+                        .value_name("DOCUMENT")
+                        .required(true)
+                        .help("Name or path of the document"),
+                )
+                .arg(
+                    Arg::with_name("window")
+                        .short("w")
+                        .long("window")
+                        .value_name("NUM")
+                        .default_value("100")
+                        .help("Number of sentences in each chunk"),
+                )
+                .arg(
+                    Arg::with_name("chapter")
+                        .short("c")
+                        .long("chapter")
+                        .value_name("REGEX")
+                        .takes_value(true)
+                        .help("Start a chunk at every sentence matching the regex, such as '^第.+章' (instead of --window)"),
+                )
+                .arg(
+                    Arg::with_name("min-count")
+                        .short("m")
+                        .long("min-count")
+                        .value_name("NUM")
+                        .default_value("1")
+                        .help("Skip words that recur fewer times after their first occurrence in the document"),
+                )
+                .arg(
+                    Arg::with_name("chunk")
+                        .long("chunk")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .help("Only list this chunk"),
+                )
+                .arg(
+                    Arg::with_name("generate")
+                        .long("generate")
+                        .requires("chunk")
+                        .help("Generate flashcards for the words of the chunk, in the order they first appear"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("list the commands that changed the database")
+                .arg(
+                    Arg::with_name("number")
+                        .short("n")
+                        .long("number")
+                        .value_name("NUM")
+                        .default_value("20")
+                        .help("Number of commands to list"),
+                )
+                .arg(
+                    Arg::with_name("verbose")
+                        .short("v")
+                        .long("verbose")
+                        .help("Show the changes of each command"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about("undo the last commands that changed the database")
+                .arg(
+                    Arg::with_name("number")
+                        .value_name("NUM")
+                        .default_value("1")
+                        .help("Number of commands to undo"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("db")
+                .about("merge, back up and restore the database")
+                .subcommand(
+                    SubCommand::with_name("merge")
+                        .about("merge another database into this one")
+                        .arg(
+                            Arg::with_name("path")
+                                .value_name("PATH")
+                                .required(true)
+                                .help("Path to the database to merge"),
+                        )
+                        .arg(
+                            Arg::with_name("policy")
+                                .short("p")
+                                .long("policy")
+                                .takes_value(true)
+                                .possible_value("ours")
+                                .possible_value("theirs")
+                                .possible_value("learned-wins")
+                                .possible_value("excluded-wins")
+                                .help("How to resolve conflicting flags, defaults to merge_policy in the config"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("backup")
+                        .about("copy the database to a file")
+                        .arg(
+                            Arg::with_name("path")
+                                .value_name("PATH")
+                                .required(true)
+                                .help("Path to write the backup to"),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .short("f")
+                                .long("force")
+                                .help("Overwrite an existing file"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("restore")
+                        .about("replace the database with a backup")
+                        .arg(
+                            Arg::with_name("path")
+                                .value_name("PATH")
+                                .required(true)
+                                .help("Path to the backup to restore"),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("config")
                 .about("generate configuration")
@@ -83,118 +649,167 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .help("Overwrite existing config"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("profile")
+                .about("manage profiles with their own database, anki deck and tokenizer")
+                .subcommand(SubCommand::with_name("list").about("list the profiles, the active profile is marked with *"))
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("create a profile")
+                        .arg(
+                            Arg::with_name("name")
+                                .value_name("NAME")
+                                .required(true)
+                                .help("Name of the profile"),
+                        )
+                        .arg(
+                            Arg::with_name("copy")
+                                .long("copy")
+                                .help("Copy the config of the active profile instead of using the default config, the database is not copied"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("switch")
+                        .about("make a profile the active profile")
+                        .arg(
+                            Arg::with_name("name")
+                                .value_name("NAME")
+                                .required(true)
+                                .help("Name of the profile"),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("list")
                 .about("list vocabulary")
                 .arg(
                     Arg::with_name("pos")
                         .long("pos")
-                        .conflicts_with_all(&["anki", "learned", "order"])
+                        .conflicts_with_all(&["anki", "learned", "order", "query", "since"])
                         .help("List pos instead of vocabulary"),
                 )
                 .arg(
-                    Arg::with_name("number")
-                        .value_name("NUM")
-                        .default_value("-1")
-                        .required(false)
-                        .help("Number of entries to list"),
+                    Arg::with_name("rank")
+                        .short("r")
+                        .long("rank")
+                        .conflicts_with("pos")
+                        .help("Show the frequency and the rank in the external frequency lists"),
                 )
                 .arg(
-                    Arg::with_name("asc")
-                        .short("a")
-                        .long("asc")
-                        .help("Sort by ascending instead of descending"),
+                    Arg::with_name("dispersion")
+                        .long("dispersion")
+                        .conflicts_with("pos")
+                        .help("Show the number of documents, the frequency per million tokens and Juilland's D"),
                 )
                 .arg(
-                    Arg::with_name("anki")
-                        .long("anki")
-                        .help("Show expressions that are already in anki"),
+                    Arg::with_name("explain")
+                        .long("explain")
+                        .conflicts_with("pos")
+                        .help("Show the score and its terms, and sort by score unless --order is given"),
                 )
+                .args(&expression_filter_arg_list()),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("export vocabulary to CSV, TSV or JSON")
                 .arg(
-                    Arg::with_name("learned")
-                        .long("learn")
-                        .help("Show expressions that have already been learned"),
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_value("csv")
+                        .possible_value("tsv")
+                        .possible_value("json")
+                        .default_value("csv")
+                        .help("File format to export"),
                 )
                 .arg(
-                    Arg::with_name("excluded")
-                        .long("exclude")
-                        .help("Show expressions that have been excluded"),
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Write to PATH instead of stdout"),
                 )
                 .arg(
-                    Arg::with_name("order")
-                        .short("o")
-                        .long("order")
-                        .takes_value(true)
-                        .possible_value("frequency")
-                        .possible_value("expression")
-                        .possible_value("id")
-                        .help("Sort by ascending instead of descending"),
-                ),
+                    Arg::with_name("sentences")
+                        .short("s")
+                        .long("sentences")
+                        .value_name("NUM")
+                        .default_value("3")
+                        .help("Number of example sentences for each expression"),
+                )
+                .args(&expression_filter_arg_list()),
         )
         .get_matches();
 
     // load the config file
-    let config_directory;
-    let config_file;
-    let home_path = dirs::home_dir().expect("Failed to get home directory");
-
-    match !cfg!(debug_assertions) {
-        // path for release
-        true => {
-            config_directory = home_path.join(".vocabulist_rs");
-            config_file = config_directory.join("config.toml");
-        }
-        // path for dev
-        false => {
-            config_directory = home_path.join(".vocabulist_rs_dev");
-            config_file = config_directory.join("config.toml");
-
-            println!("WARNING: Running in developer mode.");
-            println!(
-                "WARNING: Using {} as home directory.",
-                config_directory.to_str().unwrap()
-            );
-            println!("");
-        }
-    }
-
-    // check if the config file exists
-    let toml;
+    let config_directory = vocabulist_rs::config_root()?;
 
-    match config_file.is_file() {
-        true => {
-            toml = fs::read_to_string(config_file)?;
-        }
-        false => {
-            toml = String::new();
+    if cfg!(debug_assertions) {
+        println!("WARNING: Running in developer mode.");
+        println!(
+            "WARNING: Using {} as home directory.",
+            config_directory.display()
+        );
+        println!();
+    }
 
-            let config = Config::default(config_directory);
+    // the config file of the active profile
+    let profile = profile::active(&config_directory, match_list.value_of("profile"))?;
+    let config_file = profile::config_file(&config_directory, &profile);
 
-            match match_list.subcommand() {
-                ("config", Some(m)) => vocabulist_rs::config(config, m),
-                _ => {
-                    println!("ERROR: Configuration file does not exist.");
-                    println!("Exiting.");
-                    println!("");
-                    println!("To create a configuration file run `vocabulist_rs config`");
+    // without a config file only config and profile can run
+    if !config_file.is_file() {
+        let config = Config::default(profile::directory(&config_directory, &profile));
 
-                    Ok(())
-                }
-            }?;
+        let message = match match_list.subcommand() {
+            ("config", Some(m)) => return cli::config(config, m),
+            ("profile", Some(m)) => return cli::profile(config, m),
+            _ if profile != profile::DEFAULT => format!(
+                "The profile {} does not exist. To create it run `vocabulist_rs profile create {}`",
+                profile, profile
+            ),
+            _ => "Configuration file does not exist. To create a configuration file run `vocabulist_rs config`".to_string(),
+        };
 
-            std::process::exit(0);
-        }
+        return Err(error::Error::new(error::Kind::Config, message).into());
     }
 
-    let config: Config = toml::from_str(&toml)?;
+    let toml = fs::read_to_string(&config_file).context(
+        error::Kind::Io,
+        &format!("Failed to read {}", config_file.display()),
+    )?;
+
+    let config: Config = toml::from_str(&toml).context(
+        error::Kind::Config,
+        &format!("Invalid config {}", config_file.display()),
+    )?;
 
     match match_list.subcommand() {
-        ("import", Some(m)) => vocabulist_rs::import(config, m),
-        ("sync", Some(m)) => vocabulist_rs::sync(config, m),
-        ("list", Some(m)) => vocabulist_rs::list(config, m),
-        ("exclude", Some(m)) => vocabulist_rs::exclude(config, m),
-        ("include", Some(m)) => vocabulist_rs::include(config, m),
-        ("generate", Some(m)) => vocabulist_rs::generate(config, m),
+        ("import", Some(m)) => cli::import(config, m),
+        ("sync", Some(m)) => cli::sync(config, m),
+        ("list", Some(m)) => cli::list(config, m),
+        ("export", Some(m)) => cli::export(config, m),
+        ("exclude", Some(m)) => cli::exclude(config, m),
+        ("include", Some(m)) => cli::include(config, m),
+        ("learn", Some(m)) => cli::learn(config, m),
+        ("unlearn", Some(m)) => cli::unlearn(config, m),
+        ("priority", Some(m)) => cli::priority(config, m),
+        ("generate", Some(m)) => cli::generate(config, m),
+        ("tag", Some(m)) => cli::tag(config, m),
+        ("frequency", Some(m)) => cli::frequency(config, m),
+        ("history", Some(m)) => cli::history(config, m),
+        ("undo", Some(m)) => cli::undo(config, m),
+        ("search", Some(m)) => cli::search(config, m),
+        ("recount", Some(m)) => cli::recount(config, m),
+        ("cache", Some(m)) => cli::cache(config, m),
+        ("db", Some(m)) => cli::db(config, m),
+        ("stats", Some(m)) => cli::stats(config, m),
+        ("recommend", Some(m)) => cli::recommend(config, m),
+        ("mine", Some(m)) => cli::mine(config, m),
+        ("keywords", Some(m)) => cli::keywords(config, m),
+        ("plan", Some(m)) => cli::plan(config, m),
+        ("profile", Some(m)) => cli::profile(config, m),
         _ => Ok(()),
     }?;
 
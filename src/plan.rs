@@ -0,0 +1,133 @@
+use crate::database::stats::FirstOccurrence;
+use regex::Regex;
+
+/// How a document is split into chunks.
+pub enum Split {
+    /// every chunk has this number of sentences
+    Window(i64),
+    /// every sentence matching the regex starts a chapter
+    Chapter(Regex),
+}
+
+/// A part of a document with the unknown expressions that first appear in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub title: String,
+    /// the position of the first sentence of the chunk
+    pub start: i64,
+    pub expression_list: Vec<FirstOccurrence>,
+}
+
+impl Chunk {
+    fn new(title: String, start: i64) -> Chunk {
+        Chunk {
+            title,
+            start,
+            expression_list: Vec::new(),
+        }
+    }
+}
+
+/// Split a document into chunks and put each expression into the chunk it first appears in.
+///
+/// # Arguments
+///
+/// * `sentence_list` - The positions and sentences of the document, in document order
+/// * `occurrence_list` - The first occurrences of the expressions, in document order
+/// * `split` - Where chunks start
+pub fn chunk_list(
+    sentence_list: &[(i64, String)],
+    occurrence_list: Vec<FirstOccurrence>,
+    split: &Split,
+) -> Vec<Chunk> {
+    let (first, last) = match (sentence_list.first(), sentence_list.last()) {
+        (Some((first, _)), Some((last, _))) => (*first, *last),
+        _ => return Vec::new(),
+    };
+
+    let mut chunk_list: Vec<Chunk> = match split {
+        Split::Window(size) => {
+            let size = (*size).max(1);
+
+            (0..=(last - first) / size)
+                .map(|i| {
+                    let start = first + i * size;
+                    let end = (start + size - 1).min(last);
+
+                    Chunk::new(format!("Sentences {}-{}", start + 1, end + 1), start)
+                })
+                .collect()
+        }
+        Split::Chapter(regex) => {
+            let mut chunk_list: Vec<Chunk> = sentence_list
+                .iter()
+                .filter(|(_, sentence)| regex.is_match(sentence))
+                .map(|(position, sentence)| Chunk::new(sentence.to_string(), *position))
+                .collect();
+
+            // the text before the first chapter heading
+            if chunk_list.first().map(|x| x.start) != Some(first) {
+                chunk_list.insert(0, Chunk::new("Beginning".to_string(), first));
+            }
+
+            chunk_list
+        }
+    };
+
+    for occurrence in occurrence_list {
+        let i = chunk_list
+            .partition_point(|x| x.start <= occurrence.position)
+            .max(1);
+
+        chunk_list[i - 1].expression_list.push(occurrence);
+    }
+
+    chunk_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn occurrence(expression: &str, position: i64) -> FirstOccurrence {
+        FirstOccurrence {
+            expression: expression.to_string(),
+            position,
+            count: 2,
+            sentence: String::new(),
+        }
+    }
+
+    fn expression_list(chunk: &Chunk) -> Vec<&str> {
+        chunk
+            .expression_list
+            .iter()
+            .map(|x| x.expression.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_list() {
+        let sentence_list: Vec<(i64, String)> = ["序", "第一章", "猫だ", "第二章", "犬だ"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i as i64, x.to_string()))
+            .collect();
+        let occurrence_list = vec![occurrence("猫", 2), occurrence("犬", 4)];
+
+        let chunk_list = chunk_list(&sentence_list, occurrence_list.clone(), &Split::Window(2));
+        assert_eq!(chunk_list.len(), 3);
+        assert_eq!(chunk_list[1].title, "Sentences 3-4");
+        assert_eq!(expression_list(&chunk_list[1]), vec!["猫"]);
+        assert_eq!(chunk_list[2].title, "Sentences 5-5");
+        assert_eq!(expression_list(&chunk_list[2]), vec!["犬"]);
+
+        let split = Split::Chapter(Regex::new("^第.+章$").unwrap());
+        let chunk_list = super::chunk_list(&sentence_list, occurrence_list, &split);
+        let title_list: Vec<&str> = chunk_list.iter().map(|x| x.title.as_str()).collect();
+        assert_eq!(title_list, vec!["Beginning", "第一章", "第二章"]);
+        assert!(chunk_list[0].expression_list.is_empty());
+        assert_eq!(expression_list(&chunk_list[1]), vec!["猫"]);
+        assert_eq!(expression_list(&chunk_list[2]), vec!["犬"]);
+    }
+}
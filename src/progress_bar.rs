@@ -1,6 +1,8 @@
 use indicatif::{ProgressBar, ProgressStyle};
+use std::cell::RefCell;
+use vocabulist_rs::Progress;
 
-pub fn new(len: u64, message: &str) -> ProgressBar {
+fn new(len: u64, message: &str) -> ProgressBar {
     let pb = ProgressBar::new(len).with_style(
         ProgressStyle::default_bar()
             .template("{spinner:.black} [{bar:40.black/black}] [{pos:>7}/{len:7}] {msg}")
@@ -10,3 +12,38 @@ pub fn new(len: u64, message: &str) -> ProgressBar {
 
     pb
 }
+
+/// Shows the progress of the library on the terminal, with a progress bar for each step.
+#[derive(Default)]
+pub struct Reporter {
+    pb: RefCell<Option<ProgressBar>>,
+}
+
+impl Progress for Reporter {
+    fn message(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn start(&self, len: u64, message: &str) {
+        *self.pb.borrow_mut() = Some(new(len, message));
+    }
+
+    fn inc(&self) {
+        if let Some(pb) = self.pb.borrow().as_ref() {
+            pb.inc(1);
+        }
+    }
+
+    fn position(&self, position: u64, len: u64) {
+        if let Some(pb) = self.pb.borrow().as_ref() {
+            pb.set_length(len);
+            pb.set_position(position);
+        }
+    }
+
+    fn finish(&self, message: &str) {
+        if let Some(pb) = self.pb.borrow_mut().take() {
+            pb.finish_with_message(message);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::config::Scoring;
+use crate::database::{ExpressionRecord, ScoreScale};
+use std::cmp::Ordering;
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// The weighted terms of the score of an expression.
+///
+/// Before they are weighted the terms are scaled to be between 0 and 1,
+/// except for the priority, which is between -1 and 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    /// the log of the frequency, scaled by the log of the largest frequency
+    pub frequency: f64,
+    /// the share of documents the expression occurs in
+    pub documents: f64,
+    /// Juilland's D of the expression over the documents, 0 when it is unknown
+    pub dispersion: f64,
+    /// 1 for the first word of an external frequency list, falling with the log of the rank to 0 for unranked words
+    pub rank: f64,
+    /// 1 for an expression seen now, halving every recency_half_life days
+    pub recency: f64,
+    /// the priority, scaled by the largest absolute priority
+    pub priority: f64,
+}
+
+impl Score {
+    pub fn new(
+        record: &ExpressionRecord,
+        scale: &ScoreScale,
+        scoring: &Scoring,
+        now: i64,
+    ) -> Score {
+        let frequency = match scale.max_frequency > 0 {
+            true => {
+                (1.0 + record.frequency.max(0) as f64).ln()
+                    / (1.0 + scale.max_frequency as f64).ln()
+            }
+            false => 0.0,
+        };
+
+        let documents = match scale.document_count > 0 {
+            true => record.document_count as f64 / scale.document_count as f64,
+            false => 0.0,
+        };
+
+        let dispersion = record.dispersion.unwrap_or(0.0);
+
+        let rank = match record.rank {
+            Some(rank) if scale.max_rank > 0 => {
+                1.0 - (rank.max(1) as f64).ln() / (1.0 + scale.max_rank as f64).ln()
+            }
+            _ => 0.0,
+        };
+
+        let recency = match record.last_seen {
+            Some(last_seen) if scoring.recency_half_life() > 0.0 => {
+                let age = (now - last_seen).max(0) as f64 / SECONDS_PER_DAY;
+                0.5f64.powf(age / scoring.recency_half_life())
+            }
+            _ => 0.0,
+        };
+
+        let priority = match scale.max_priority > 0 {
+            true => record.priority as f64 / scale.max_priority as f64,
+            false => 0.0,
+        };
+
+        Score {
+            frequency: frequency * scoring.frequency(),
+            documents: documents * scoring.documents(),
+            dispersion: dispersion * scoring.dispersion(),
+            rank: rank * scoring.rank(),
+            recency: recency * scoring.recency(),
+            priority: priority * scoring.priority(),
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.frequency + self.documents + self.dispersion + self.rank + self.recency + self.priority
+    }
+
+    /// The terms of the score, for explaining it.
+    pub fn term_list(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("frequency", self.frequency),
+            ("documents", self.documents),
+            ("dispersion", self.dispersion),
+            ("rank", self.rank),
+            ("recency", self.recency),
+            ("priority", self.priority),
+        ]
+    }
+}
+
+/// Score each record.
+pub fn score_list(
+    record_list: Vec<ExpressionRecord>,
+    scale: &ScoreScale,
+    scoring: &Scoring,
+    now: i64,
+) -> Vec<(ExpressionRecord, Score)> {
+    record_list
+        .into_iter()
+        .map(|record| {
+            let score = Score::new(&record, scale, scoring, now);
+            (record, score)
+        })
+        .collect()
+}
+
+/// Sort scored records by their total score, highest first unless is_asc is set.
+///
+/// Records with the same score keep their order.
+pub fn sort(scored_list: &mut [(ExpressionRecord, Score)], is_asc: bool) {
+    scored_list.sort_by(|(_, a), (_, b)| {
+        let ordering = b.total().partial_cmp(&a.total()).unwrap_or(Ordering::Equal);
+
+        match is_asc {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        expression: &str,
+        frequency: i64,
+        rank: Option<i64>,
+        priority: i64,
+    ) -> ExpressionRecord {
+        ExpressionRecord {
+            id: 0,
+            expression: expression.to_string(),
+            frequency,
+            is_excluded: false,
+            in_anki: false,
+            is_learned: false,
+            rank,
+            document_count: 1,
+            last_seen: Some(0),
+            priority,
+            per_million: 0.0,
+            dispersion: Some(0.5),
+        }
+    }
+
+    fn scale() -> ScoreScale {
+        ScoreScale {
+            max_frequency: 9,
+            document_count: 2,
+            max_rank: 99,
+            max_priority: 2,
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        let scoring: Scoring = toml::from_str(
+            "frequency = 1.0\ndocuments = 0.5\ndispersion = 2.0\nrank = 2.0\nrecency = 1.0\npriority = 1.0\nrecency_half_life = 10",
+        )
+        .unwrap();
+
+        let score = Score::new(&record("猫", 9, Some(1), 1), &scale(), &scoring, 10 * 86400);
+
+        assert!((score.frequency - 1.0).abs() < 1e-9);
+        assert!((score.documents - 0.25).abs() < 1e-9);
+        assert!((score.dispersion - 1.0).abs() < 1e-9);
+        assert!((score.rank - 2.0).abs() < 1e-9);
+        assert!((score.recency - 0.5).abs() < 1e-9);
+        assert!((score.priority - 0.5).abs() < 1e-9);
+        assert!((score.total() - 5.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sort() {
+        let scoring = Scoring::default();
+
+        let mut scored_list = score_list(
+            vec![
+                record("猫", 9, None, 0),
+                record("犬", 3, None, 0),
+                record("鳥", 1, None, 2),
+            ],
+            &scale(),
+            &scoring,
+            0,
+        );
+        sort(&mut scored_list, false);
+
+        let expression_list: Vec<&str> = scored_list
+            .iter()
+            .map(|(record, _)| record.expression.as_str())
+            .collect();
+
+        // the default weights order by frequency, and a priority of the largest priority adds as much as the largest frequency
+        assert_eq!(expression_list, vec!["鳥", "猫", "犬"]);
+    }
+}
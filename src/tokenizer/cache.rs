@@ -0,0 +1,174 @@
+use super::Token;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::PathBuf;
+
+const CREATE_TOKEN_CACHE: &str = "CREATE TABLE IF NOT EXISTS token_cache (
+                backend TEXT NOT NULL,
+                sentence_hash TEXT NOT NULL,
+                token_list TEXT NOT NULL,
+                PRIMARY KEY (backend, sentence_hash)
+                );";
+
+const SELECT_TOKEN_LIST: &str =
+    "SELECT token_list FROM token_cache WHERE backend = ? AND sentence_hash = ?;";
+
+const INSERT_TOKEN_LIST: &str =
+    "INSERT OR REPLACE INTO token_cache (backend, sentence_hash, token_list) VALUES (?, ?, ?);";
+
+const SELECT_BACKEND_COUNT_LIST: &str =
+    "SELECT backend, COUNT(*) FROM token_cache GROUP BY backend ORDER BY backend ASC;";
+
+const DELETE_TOKEN_LIST: &str = "DELETE FROM token_cache;";
+
+/// hash a sentence so the cache does not have to index the full text
+fn sentence_hash(sentence: &str) -> String {
+    format!("{:x}", Sha256::digest(sentence.as_bytes()))
+}
+
+/// statistics about the contents of the cache
+pub struct CacheStats {
+    pub backend_list: Vec<(String, i64)>,
+    pub size: u64,
+}
+
+impl CacheStats {
+    /// the total number of cached sentences
+    pub fn entry_count(&self) -> i64 {
+        self.backend_list.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// an on-disk cache of tokenizer output
+///     keyed by the backend identifier and the sentence hash
+pub struct Cache {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl Cache {
+    /// open the cache at path, creating it if it does not exist
+    pub fn connect(path: &PathBuf) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute(CREATE_TOKEN_CACHE, params![])?;
+
+        Ok(Cache {
+            conn,
+            path: path.clone(),
+        })
+    }
+
+    /// get the cached token list for a sentence
+    pub fn select(
+        &self,
+        backend: &str,
+        sentence: &str,
+    ) -> Result<Option<Vec<Token>>, Box<dyn Error>> {
+        let mut statement = self.conn.prepare_cached(SELECT_TOKEN_LIST)?;
+        let token_list: Option<String> = statement
+            .query_row(params![backend, sentence_hash(sentence)], |row| row.get(0))
+            .optional()?;
+
+        match token_list {
+            Some(token_list) => {
+                let token_list: Vec<Token> = serde_json::from_str(&token_list)?;
+                let token_list = token_list
+                    .into_iter()
+                    .map(|x| x.sentence(vec![sentence.to_string()]))
+                    .collect();
+
+                Ok(Some(token_list))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// store the token lists for a list of sentences in a single transaction
+    pub fn insert_list(
+        &self,
+        backend: &str,
+        sentence_list: &[(String, Vec<Token>)],
+    ) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        {
+            let mut statement = tx.prepare_cached(INSERT_TOKEN_LIST)?;
+            for (sentence, token_list) in sentence_list.iter() {
+                let token_list = serde_json::to_string(token_list)?;
+                statement.execute(params![backend, sentence_hash(sentence), token_list])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// get the number of cached sentences per backend and the size of the cache file
+    pub fn stats(&self) -> Result<CacheStats, Box<dyn Error>> {
+        let mut statement = self.conn.prepare(SELECT_BACKEND_COUNT_LIST)?;
+        let backend_list = statement
+            .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+        let size = match self.path.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        Ok(CacheStats { backend_list, size })
+    }
+
+    /// remove every entry from the cache and return the number of entries removed
+    pub fn clear(&self) -> Result<usize, Box<dyn Error>> {
+        let count = self.conn.execute(DELETE_TOKEN_LIST, params![])?;
+        self.conn.execute_batch("VACUUM;")?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_list() -> Vec<Token> {
+        vec![
+            Token::new("名前".to_string())
+                .pos(vec!["名詞".to_string()])
+                .sentence(vec!["名前は".to_string()])
+                .surface_string(vec!["名前".to_string()]),
+            Token::new("は".to_string())
+                .pos(vec!["助詞".to_string()])
+                .sentence(vec!["名前は".to_string()])
+                .surface_string(vec!["は".to_string()]),
+        ]
+    }
+
+    /// assert that a stored token list is returned only for the same backend
+    #[test]
+    fn test_select_insert() {
+        let cache = Cache::connect(&PathBuf::from(":memory:")).expect("Failed to open cache");
+
+        cache
+            .insert_list("mecab 0.996", &[("名前は".to_string(), token_list())])
+            .expect("Failed to insert token list");
+
+        let cached = cache
+            .select("mecab 0.996", "名前は")
+            .expect("Failed to select token list");
+        assert_eq!(cached, Some(token_list()));
+
+        let cached = cache
+            .select("jumanpp 2.0.0", "名前は")
+            .expect("Failed to select token list");
+        assert_eq!(cached, None);
+
+        let stats = cache.stats().expect("Failed to get stats");
+        assert_eq!(stats.entry_count(), 1);
+
+        assert_eq!(cache.clear().expect("Failed to clear cache"), 1);
+        assert_eq!(cache.stats().expect("Failed to get stats").entry_count(), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Token, Tokenize};
+use crate::error::{self, Context};
 use std::error::Error;
 use std::io::Write;
 use std::path::PathBuf;
@@ -24,6 +25,15 @@ fn jumanpp(path: &PathBuf, sentence: &str) -> Result<String, Box<dyn Error>> {
     Ok(token_string)
 }
 
+/// get the version string of the jumanpp binary at path
+fn jumanpp_version(path: &PathBuf) -> Result<String, Box<dyn Error>> {
+    let output = Command::new(path).arg("-v").output()?;
+
+    let version_string = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    Ok(version_string)
+}
+
 /// convert the output from jumanpp to a list of Token structs
 fn tokenize_output(sentence: &str, output: &str) -> Vec<Token> {
     let output_list: Vec<Vec<&str>> = output.lines().map(|x| x.split(' ').collect()).collect();
@@ -61,11 +71,30 @@ impl Jumanpp {
 impl Tokenize for Jumanpp {
     /// implement the required method for Tokenize
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>> {
-        let token_string = jumanpp(&self.path, sentence)?;
+        let token_string = jumanpp(&self.path, sentence).context(
+            error::Kind::Tokenizer,
+            &format!(
+                "Failed to run {}, check that it is installed",
+                self.path.display()
+            ),
+        )?;
         let token_list = tokenize_output(sentence, token_string.as_ref());
 
         Ok(token_list)
     }
+
+    /// identify the backend by the jumanpp version
+    fn identifier(&self) -> Result<String, Box<dyn Error>> {
+        let version = jumanpp_version(&self.path).context(
+            error::Kind::Tokenizer,
+            &format!(
+                "Failed to run {}, check that it is installed",
+                self.path.display()
+            ),
+        )?;
+
+        Ok(format!("jumanpp {}", version))
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 use super::{Token, Tokenize};
+use crate::error::{self, Context};
 use regex::Regex;
 use std::error::Error;
 use std::io::Write;
@@ -17,11 +18,29 @@ impl Mecab {
 
 impl Tokenize for Mecab {
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>> {
-        let output = tokenize_sentence(&self.path, sentence)?;
+        let output = tokenize_sentence(&self.path, sentence).context(
+            error::Kind::Tokenizer,
+            &format!(
+                "Failed to run {}, check that it is installed",
+                self.path.display()
+            ),
+        )?;
         let token_list = output_to_token_list(output, sentence);
 
         Ok(token_list)
     }
+
+    fn identifier(&self) -> Result<String, Box<dyn Error>> {
+        let version = mecab_version(&self.path).context(
+            error::Kind::Tokenizer,
+            &format!(
+                "Failed to run {}, check that it is installed",
+                self.path.display()
+            ),
+        )?;
+
+        Ok(format!("mecab {}", version))
+    }
 }
 
 fn tokenize_sentence(path: &PathBuf, sentence: &str) -> Result<String, Box<dyn Error>> {
@@ -45,6 +64,22 @@ fn tokenize_sentence(path: &PathBuf, sentence: &str) -> Result<String, Box<dyn E
     Ok(output)
 }
 
+fn mecab_version(path: &PathBuf) -> Result<String, Box<dyn Error>> {
+    // the version of the binary
+    let version = Command::new(path).arg("-v").output()?;
+    let version = String::from_utf8_lossy(&version.stdout).trim().to_string();
+
+    // the dictionary in use, which changes the output as much as the binary does
+    let dictionary = Command::new(path).arg("-D").output()?;
+    let dictionary = String::from_utf8_lossy(&dictionary.stdout)
+        .lines()
+        .map(|x| x.trim())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    Ok(format!("{} {}", version, dictionary))
+}
+
 fn output_to_token_list(output_string: String, sentence: &str) -> Vec<Token> {
     let re = Regex::new(r"[,\t]").unwrap();
 
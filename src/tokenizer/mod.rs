@@ -1,8 +1,10 @@
+pub mod cache;
 pub mod jumanpp;
 pub mod mecab;
 pub mod token;
 pub mod tokenize;
 
+use cache::Cache;
 use std::error::Error;
 use token::Token;
 use tokenize::Tokenize;
@@ -13,6 +15,7 @@ where
     T: Tokenize,
 {
     tokenizer: T,
+    cache: Option<Cache>,
 }
 
 impl<T> Tokenizer<T>
@@ -25,23 +28,67 @@ where
     where
         T: Tokenize,
     {
-        Tokenizer { tokenizer }
+        Tokenizer {
+            tokenizer,
+            cache: None,
+        }
+    }
+
+    /// consult cache before running the backend
+    pub fn cache(self, cache: Cache) -> Self {
+        Tokenizer {
+            tokenizer: self.tokenizer,
+            cache: Some(cache),
+        }
     }
 
     /// tokenize a list of sentences and return a list of Token structs
+    ///     numbered by their position in the sentence
     pub fn tokenize(
         &self,
-        sentence_list: &Vec<String>,
+        sentence_list: &[String],
         callback: &mut dyn FnMut(),
     ) -> Result<Vec<Token>, Box<dyn Error>> {
+        let cache = match &self.cache {
+            Some(cache) => Some((cache, self.tokenizer.identifier()?)),
+            None => None,
+        };
+
         let mut token_list: Vec<Token> = Vec::new();
+        let mut uncached_list: Vec<(String, Vec<Token>)> = Vec::new();
         for sentence in sentence_list.iter() {
-            let list = self.tokenizer.tokenize(sentence)?;
-            token_list.extend(list);
+            let cached = match &cache {
+                Some((cache, backend)) => cache.select(backend, sentence)?,
+                None => None,
+            };
+
+            let list = match cached {
+                Some(list) => list,
+                None => {
+                    let list = self.tokenizer.tokenize(sentence)?;
+
+                    if cache.is_some() {
+                        uncached_list.push((sentence.to_string(), list.clone()));
+                    }
+
+                    list
+                }
+            };
+
+            // number the tokens by their position in the sentence
+            token_list.extend(
+                list.into_iter()
+                    .enumerate()
+                    .map(|(i, token)| token.position(i)),
+            );
 
             callback();
         }
 
+        if let Some((cache, backend)) = &cache {
+            cache.insert_list(backend, &uncached_list)?;
+        }
+
         Ok(token_list)
     }
 }
@@ -148,6 +195,27 @@ mod tests {
                 _ => Ok(token_list),
             }
         }
+
+        /// implement identifier for the Backend struct
+        fn identifier(&self) -> Result<String, Box<dyn Error>> {
+            Ok("backend".to_string())
+        }
+    }
+
+    /// mock struct that counts calls to the backend
+    struct CountingBackend {
+        called: std::cell::Cell<i32>,
+    }
+
+    impl Tokenize for CountingBackend {
+        fn tokenize(&self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+            self.called.set(self.called.get() + 1);
+            Backend.tokenize(sentence)
+        }
+
+        fn identifier(&self) -> Result<String, Box<dyn Error>> {
+            Backend.identifier()
+        }
     }
 
     /// return a list of tokens to compare to the result of tokenze
@@ -157,79 +225,92 @@ mod tests {
             Token::new("魅力".to_string())
                 .pos(vec!["名詞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["魅力".to_string()]),
+                .surface_string(vec!["魅力".to_string()])
+                .position(0),
         );
         expected_token_list.push(
             Token::new("が".to_string())
                 .pos(vec!["助詞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["が".to_string()]),
+                .surface_string(vec!["が".to_string()])
+                .position(1),
         );
         expected_token_list.push(
             Token::new("たっぷりだ".to_string())
                 .pos(vec!["形容詞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["たっぷり".to_string()]),
+                .surface_string(vec!["たっぷり".to_string()])
+                .position(2),
         );
         expected_token_list.push(
             Token::new("と".to_string())
                 .pos(vec!["助詞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["と".to_string()]),
+                .surface_string(vec!["と".to_string()])
+                .position(3),
         );
         expected_token_list.push(
             Token::new("詰まる".to_string())
                 .pos(vec!["動詞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["詰まって".to_string()]),
+                .surface_string(vec!["詰まって".to_string()])
+                .position(4),
         );
         expected_token_list.push(
             Token::new("いる".to_string())
                 .pos(vec!["接尾辞".to_string()])
                 .sentence(vec!["魅力がたっぷりと詰まっている".to_string()])
-                .surface_string(vec!["いる".to_string()]),
+                .surface_string(vec!["いる".to_string()])
+                .position(5),
         );
         expected_token_list.push(
             Token::new("はる".to_string())
                 .pos(vec!["名詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["はる".to_string()]),
+                .surface_string(vec!["はる".to_string()])
+                .position(0),
         );
         expected_token_list.push(
             Token::new("さん".to_string())
                 .pos(vec!["接尾辞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["さん".to_string()]),
+                .surface_string(vec!["さん".to_string()])
+                .position(1),
         );
         expected_token_list.push(
             Token::new("ハウス".to_string())
                 .pos(vec!["名詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["ハウス".to_string()]),
+                .surface_string(vec!["ハウス".to_string()])
+                .position(2),
         );
         expected_token_list.push(
             Token::new("は".to_string())
                 .pos(vec!["助詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["は".to_string()]),
+                .surface_string(vec!["は".to_string()])
+                .position(3),
         );
         expected_token_list.push(
             Token::new("どこ".to_string())
                 .pos(vec!["指示詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["どこ".to_string()]),
+                .surface_string(vec!["どこ".to_string()])
+                .position(4),
         );
         expected_token_list.push(
             Token::new("だ".to_string())
                 .pos(vec!["判定詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["です".to_string()]),
+                .surface_string(vec!["です".to_string()])
+                .position(5),
         );
         expected_token_list.push(
             Token::new("か".to_string())
                 .pos(vec!["助詞".to_string()])
                 .sentence(vec!["はるさんハウスはどこですか".to_string()])
-                .surface_string(vec!["か".to_string()]),
+                .surface_string(vec!["か".to_string()])
+                .position(6),
         );
 
         expected_token_list
@@ -258,4 +339,31 @@ mod tests {
         assert_eq!(token_list, expected_token_list);
         assert_eq!(called, 2);
     }
+
+    /// assert that cached sentences are not passed to the backend a second time
+    #[test]
+    fn test_tokenize_cache() {
+        let sentence_list = vec![
+            "魅力がたっぷりと詰まっている".to_string(),
+            "はるさんハウスはどこですか".to_string(),
+        ];
+
+        let cache =
+            Cache::connect(&std::path::PathBuf::from(":memory:")).expect("Failed to open cache");
+        let backend = CountingBackend {
+            called: std::cell::Cell::new(0),
+        };
+        let tokenizer = Tokenizer::new(backend).cache(cache);
+
+        let first = tokenizer
+            .tokenize(&sentence_list, &mut || {})
+            .expect("Failed to unwrap token_list");
+        let second = tokenizer
+            .tokenize(&sentence_list, &mut || {})
+            .expect("Failed to unwrap token_list");
+
+        assert_eq!(first, expected_token_list());
+        assert_eq!(second, expected_token_list());
+        assert_eq!(tokenizer.tokenizer.called.get(), 2);
+    }
 }
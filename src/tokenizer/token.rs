@@ -1,9 +1,14 @@
-#[derive(PartialEq, Debug, Clone, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Token {
     token: String,
     pos: Vec<String>,
+    #[serde(skip)]
     sentence: Vec<String>,
     surface_string: Vec<String>,
+    #[serde(skip)]
+    position: usize,
 
     reading: Vec<String>,
     definition: Vec<String>,
@@ -23,6 +28,7 @@ impl Token {
             pos: pos,
             sentence: self.sentence,
             surface_string: self.surface_string,
+            position: self.position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -34,6 +40,7 @@ impl Token {
             pos: self.pos,
             sentence: sentence,
             surface_string: self.surface_string,
+            position: self.position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -45,6 +52,19 @@ impl Token {
             pos: self.pos,
             sentence: self.sentence,
             surface_string: surface_string,
+            position: self.position,
+            reading: self.reading,
+            definition: self.definition,
+        }
+    }
+
+    pub fn position(self, position: usize) -> Token {
+        Token {
+            token: self.token,
+            pos: self.pos,
+            sentence: self.sentence,
+            surface_string: self.surface_string,
+            position,
             reading: self.reading,
             definition: self.definition,
         }
@@ -65,4 +85,8 @@ impl Token {
     pub fn get_pos(&self) -> &Vec<String> {
         &self.pos
     }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
 }
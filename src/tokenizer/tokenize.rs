@@ -3,10 +3,18 @@ use std::error::Error;
 
 pub trait Tokenize {
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>>;
+
+    /// a string identifying the backend, its version and its dictionary
+    ///     used to key the tokenization cache
+    fn identifier(&self) -> Result<String, Box<dyn Error>>;
 }
 
 impl Tokenize for Box<dyn Tokenize> {
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>> {
         self.as_ref().tokenize(sentence)
     }
+
+    fn identifier(&self) -> Result<String, Box<dyn Error>> {
+        self.as_ref().identifier()
+    }
 }
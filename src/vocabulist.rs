@@ -0,0 +1,1150 @@
+use crate::config::Config;
+use crate::database::filter::Filter;
+use crate::database::history::{Change, HistoryEntry, UndoSummary};
+use crate::database::merge::{MergePolicy, MergeSummary};
+use crate::database::stats::{self, Coverage, Difficulty, Keyword, Stats};
+use crate::database::{self, ExpressionRecord, MinedExpression};
+use crate::dictionary;
+use crate::error::{self, Context};
+use crate::exclusion;
+use crate::export::ExportRecord;
+use crate::expression::Expression;
+use crate::frequency_list;
+use crate::plan::{self, Chunk, Split};
+use crate::scoring::{self, Score};
+use crate::tokenizer::cache::{Cache, CacheStats};
+use crate::tokenizer::jumanpp::Jumanpp;
+use crate::tokenizer::mecab::Mecab;
+use crate::tokenizer::tokenize::Tokenize;
+use crate::tokenizer::Tokenizer;
+use crate::word_list::Column;
+use crate::{anki, date};
+use itertools::Itertools;
+use rusqlite::Connection;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Reports the progress of long running operations, every method does nothing by default.
+pub trait Progress {
+    /// A line of status, such as the file that is being imported.
+    fn message(&self, _message: &str) {}
+
+    /// A step with len items starts.
+    fn start(&self, _len: u64, _message: &str) {}
+
+    /// One item of the current step is done.
+    fn inc(&self) {}
+
+    /// The current step is at position of len items, for steps whose length is only known while they run.
+    fn position(&self, _position: u64, _len: u64) {}
+
+    /// The current step is done.
+    fn finish(&self, _message: &str) {}
+}
+
+impl Progress for () {}
+
+/// How expressions are sorted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    Frequency,
+    Expression,
+    Id,
+    /// the best rank in the external frequency lists
+    Rank,
+    /// the mean of the rank and the frequency rank
+    Combined,
+    /// the scoring in the config
+    Score,
+    /// the last time the expression was seen
+    Recent,
+    /// the number of documents
+    Range,
+    /// Juilland's D
+    Dispersion,
+}
+
+impl Order {
+    pub fn parse(string: &str) -> Result<Order, error::Error> {
+        match string {
+            "frequency" => Ok(Order::Frequency),
+            "expression" => Ok(Order::Expression),
+            "id" => Ok(Order::Id),
+            "rank" => Ok(Order::Rank),
+            "combined" => Ok(Order::Combined),
+            "score" => Ok(Order::Score),
+            "recent" => Ok(Order::Recent),
+            "range" => Ok(Order::Range),
+            "dispersion" => Ok(Order::Dispersion),
+            _ => Err(error::Error::new(
+                error::Kind::Other,
+                format!("Unknown order: {}", string),
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Order::Frequency => "frequency",
+            Order::Expression => "expression",
+            Order::Id => "id",
+            Order::Rank => "rank",
+            Order::Combined => "combined",
+            Order::Score => "score",
+            Order::Recent => "recent",
+            Order::Range => "range",
+            Order::Dispersion => "dispersion",
+        }
+    }
+}
+
+/// How documents are imported.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    is_cached: bool,
+    seen_at: Option<i64>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions::new()
+    }
+}
+
+impl ImportOptions {
+    pub fn new() -> ImportOptions {
+        ImportOptions {
+            is_cached: true,
+            seen_at: None,
+        }
+    }
+
+    /// Use the tokenizer cache, on by default.
+    pub fn cache(self, is_cached: bool) -> ImportOptions {
+        ImportOptions { is_cached, ..self }
+    }
+
+    /// The unix timestamp the expressions of the documents were seen at, now by default.
+    pub fn seen_at(self, seen_at: Option<i64>) -> ImportOptions {
+        ImportOptions { seen_at, ..self }
+    }
+}
+
+/// What an import added to the database.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportSummary {
+    pub document_count: usize,
+    pub sentence_count: usize,
+    /// the number of tokens that were imported, tokens of sentences that were imported before are skipped
+    pub token_count: usize,
+    /// the number of new expressions excluded by the exclusion rules
+    pub excluded_count: usize,
+}
+
+/// What a sync changed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SyncSummary {
+    /// the number of expressions in anki
+    pub expression_count: usize,
+    /// the number of expressions that became learned
+    pub learned_count: usize,
+    /// the number of expressions a sync had marked as learned whose interval fell below learned_interval
+    pub unlearned_count: usize,
+}
+
+/// How a frequency list is read from a delimited file, a Yomitan zip has its own format.
+#[derive(Debug, Clone)]
+pub struct FrequencyListOptions {
+    name: Option<String>,
+    column: Column,
+    reading_column: Option<Column>,
+    rank_column: Option<Column>,
+    delimiter: Option<u8>,
+    has_header: bool,
+}
+
+impl Default for FrequencyListOptions {
+    fn default() -> Self {
+        FrequencyListOptions::new()
+    }
+}
+
+impl FrequencyListOptions {
+    pub fn new() -> FrequencyListOptions {
+        FrequencyListOptions {
+            name: None,
+            column: Column::Index(1),
+            reading_column: None,
+            rank_column: None,
+            delimiter: None,
+            has_header: false,
+        }
+    }
+
+    /// The name of the list, the title of a Yomitan dictionary or the file name by default.
+    pub fn name(self, name: Option<String>) -> FrequencyListOptions {
+        FrequencyListOptions { name, ..self }
+    }
+
+    /// The column of the words, the first column by default.
+    pub fn column(self, column: Column) -> FrequencyListOptions {
+        FrequencyListOptions { column, ..self }
+    }
+
+    pub fn reading_column(self, reading_column: Option<Column>) -> FrequencyListOptions {
+        FrequencyListOptions {
+            reading_column,
+            ..self
+        }
+    }
+
+    /// The column of the ranks, without one the words are ranked in the order of the file.
+    pub fn rank_column(self, rank_column: Option<Column>) -> FrequencyListOptions {
+        FrequencyListOptions {
+            rank_column,
+            ..self
+        }
+    }
+
+    /// The field delimiter, a tab for .tsv and .txt files and a comma otherwise by default.
+    pub fn delimiter(self, delimiter: Option<u8>) -> FrequencyListOptions {
+        FrequencyListOptions { delimiter, ..self }
+    }
+
+    /// Skip the first row, which is implied by a column name.
+    pub fn header(self, has_header: bool) -> FrequencyListOptions {
+        FrequencyListOptions { has_header, ..self }
+    }
+}
+
+/// How sentences are searched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Search {
+    /// the words of a phrase, adjacent in the sentence
+    Phrase,
+    /// a substring of the sentence
+    Substring,
+    /// every surface string of an expression
+    Lemma,
+}
+
+/// An expression to create a flashcard for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub expression: String,
+    /// the sentence of the card, a sentence of the expression from the database if None
+    pub sentence: Option<String>,
+}
+
+/// The unknown expressions to learn to reach a coverage of a document.
+#[derive(Debug)]
+pub struct Recommendation {
+    pub coverage: Coverage,
+    /// the number of the expressions to learn, None if the target cannot be reached
+    pub cards_needed: Option<usize>,
+    /// the unknown expressions of the document with their number of tokens, most frequent first
+    pub expression_list: Vec<(String, i64)>,
+}
+
+/// A vocabulary database with its config.
+///
+/// This is the interface for using vocabulist as a library, the command line is a layer over it.
+pub struct Vocabulist {
+    config: Config,
+    conn: Connection,
+    command_line: Option<String>,
+}
+
+impl Vocabulist {
+    /// Open the database of the config, creating and migrating it if necessary.
+    pub fn open(config: Config) -> Result<Vocabulist, error::Error> {
+        let conn = database::connect(config.database_path())?;
+
+        Ok(Vocabulist {
+            config,
+            conn,
+            command_line: None,
+        })
+    }
+
+    /// The command line to record in the history for every change, instead of a description of the operation.
+    pub fn command_line(self, command_line: &str) -> Vocabulist {
+        Vocabulist {
+            command_line: Some(command_line.to_string()),
+            ..self
+        }
+    }
+
+    /// The text of a change in the history.
+    fn description(&self, description: String) -> String {
+        match &self.command_line {
+            Some(command_line) => command_line.to_string(),
+            None => description,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn tokenizer(
+        &self,
+        options: &ImportOptions,
+    ) -> Result<Tokenizer<Box<dyn Tokenize>>, error::Error> {
+        let backend: Box<dyn Tokenize> = match self.config.backend() {
+            "jumanpp" => Box::new(Jumanpp::new(PathBuf::from("jumanpp"))),
+            _ => Box::new(Mecab::new(PathBuf::from("mecab"))),
+        };
+
+        match options.is_cached {
+            true => Ok(Tokenizer::new(backend).cache(Cache::connect(&self.config.cache_path())?)),
+            false => Ok(Tokenizer::new(backend)),
+        }
+    }
+
+    /// Tokenize the sentences, store them and record them as a document.
+    ///
+    /// Returns the number of tokens that were imported.
+    fn import_sentence_list(
+        &mut self,
+        name: &str,
+        path: &str,
+        sentence_list: &[String],
+        tokenizer: &Tokenizer<Box<dyn Tokenize>>,
+        seen_at: i64,
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        progress.start(sentence_list.len() as u64, "Tokenizing");
+        let mut callback = || progress.inc();
+
+        let expression_list =
+            crate::token_list_to_expression_list(tokenizer.tokenize(sentence_list, &mut callback)?);
+        progress.finish("Tokenized");
+
+        let duplicate_sentence_list =
+            database::select_imported_sentence_list(&self.conn, sentence_list)?;
+        let expression_list =
+            database::filter_imported_expression_list(&duplicate_sentence_list, expression_list);
+        let token_count = expression_list.len();
+
+        progress.start(token_count as u64, "Importing");
+        database::insert_expression_list(&mut self.conn, expression_list, &|| progress.inc())?;
+        database::insert_document(&mut self.conn, name, path, sentence_list, seen_at)?;
+        progress.finish("Imported");
+
+        Ok(token_count)
+    }
+
+    /// Exclude the new expressions that match the exclusion rules in the config.
+    fn exclude_new_expression_list(&mut self, min_id: i64) -> Result<usize, error::Error> {
+        let rule_list = exclusion::compile_rule_list(self.config.exclusion_rules())?;
+        if rule_list.is_empty() {
+            return Ok(0);
+        }
+
+        let candidate_list = database::select_exclusion_candidate_list(&self.conn, min_id)?;
+        let match_list = exclusion::match_list(&rule_list, candidate_list);
+
+        if !match_list.is_empty() {
+            let description = self.description(format!(
+                "exclude {} new expressions by the exclusion rules",
+                match_list.len()
+            ));
+            crate::exclude_match_list(&mut self.conn, &description, &match_list)?;
+        }
+
+        Ok(match_list.len())
+    }
+
+    /// Import a text file, or every file in a directory.
+    pub fn import_path(
+        &mut self,
+        path: &Path,
+        options: &ImportOptions,
+        progress: &dyn Progress,
+    ) -> Result<ImportSummary, error::Error> {
+        // compile the rules before importing, so an invalid rule fails early
+        exclusion::compile_rule_list(self.config.exclusion_rules())?;
+
+        let tokenizer = self.tokenizer(options)?;
+        let description = self.description(format!("import {}", path.display()));
+        database::history::record_irreversible(&self.conn, &description)?;
+        let seen_at = match options.seen_at {
+            Some(seen_at) => seen_at,
+            None => date::now()?,
+        };
+        let min_id = database::select_max_expression_id(&self.conn)?;
+
+        let path_list: Vec<PathBuf> = match path.is_dir() {
+            true => fs::read_dir(path)
+                .and_then(|x| x.map(|entry| entry.map(|x| x.path())).collect())
+                .context(
+                    error::Kind::Io,
+                    &format!("Failed to list the files in {}", path.display()),
+                )?,
+            false => vec![path.to_path_buf()],
+        };
+
+        let mut summary = ImportSummary::default();
+        for path in path_list.iter() {
+            progress.message(&format!("Importing {}", path.display()));
+
+            let message = format!("Failed to read {}", path.display());
+            let contents = fs::read_to_string(path).context(error::Kind::Io, &message)?;
+            let sentence_list = crate::sentence_list(&contents);
+            let name = path
+                .file_name()
+                .map_or(path.to_string_lossy().to_string(), |x| {
+                    x.to_string_lossy().to_string()
+                });
+            let document_path = fs::canonicalize(path).context(error::Kind::Io, &message)?;
+
+            summary.token_count += self.import_sentence_list(
+                &name,
+                &document_path.to_string_lossy(),
+                &sentence_list,
+                &tokenizer,
+                seen_at,
+                progress,
+            )?;
+            summary.sentence_count += sentence_list.len();
+            summary.document_count += 1;
+        }
+
+        summary.excluded_count = self.exclude_new_expression_list(min_id)?;
+
+        Ok(summary)
+    }
+
+    /// Import a text as a document, importing a text with the same name again replaces the document.
+    pub fn import_text(
+        &mut self,
+        name: &str,
+        text: &str,
+        options: &ImportOptions,
+        progress: &dyn Progress,
+    ) -> Result<ImportSummary, error::Error> {
+        let tokenizer = self.tokenizer(options)?;
+        let seen_at = match options.seen_at {
+            Some(seen_at) => seen_at,
+            None => date::now()?,
+        };
+        let min_id = database::select_max_expression_id(&self.conn)?;
+
+        let description = self.description(format!("import {}", name));
+        database::history::record_irreversible(&self.conn, &description)?;
+
+        let sentence_list = crate::sentence_list(text);
+        let token_count =
+            self.import_sentence_list(name, name, &sentence_list, &tokenizer, seen_at, progress)?;
+
+        Ok(ImportSummary {
+            document_count: 1,
+            sentence_count: sentence_list.len(),
+            token_count,
+            excluded_count: self.exclude_new_expression_list(min_id)?,
+        })
+    }
+
+    /// Select the expressions matching the filter with their scores, a limit of -1 selects every expression.
+    ///
+    /// The database does not compute the score, so ordering by score sorts every matching record in memory.
+    pub fn expressions(
+        &self,
+        filter: &Filter,
+        order: Order,
+        is_asc: bool,
+        limit: i32,
+    ) -> Result<Vec<(ExpressionRecord, Score)>, error::Error> {
+        let scale = database::select_score_scale(&self.conn)?;
+        let scoring = self.config.scoring();
+        let now = date::now()?;
+
+        match order {
+            Order::Score => {
+                let record_list = database::select_expression_record_list(
+                    &self.conn,
+                    filter,
+                    Order::Frequency.as_str(),
+                    false,
+                    -1,
+                )?;
+
+                let mut scored_list = scoring::score_list(record_list, &scale, &scoring, now);
+                scoring::sort(&mut scored_list, is_asc);
+
+                if limit > -1 {
+                    scored_list.truncate(limit as usize);
+                }
+
+                Ok(scored_list)
+            }
+            order => {
+                let record_list = database::select_expression_record_list(
+                    &self.conn,
+                    filter,
+                    order.as_str(),
+                    is_asc,
+                    limit,
+                )?;
+
+                Ok(scoring::score_list(record_list, &scale, &scoring, now))
+            }
+        }
+    }
+
+    /// Create flashcards in anki for the first number expressions matching the filter that have a definition.
+    ///
+    /// Expressions without a definition are excluded. Returns the number of flashcards that were created.
+    pub fn generate(
+        &mut self,
+        number: i32,
+        filter: &Filter,
+        order: Order,
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        let dict = dictionary::connect(&self.config.dictionary_path())?;
+
+        // expressions without a definition are skipped, so select every expression that matches
+        let expression_list: Vec<Expression> = self
+            .expressions(filter, order, false, -1)?
+            .into_iter()
+            .map(|(record, _)| Expression::new(record.expression))
+            .collect();
+
+        progress.start(number as u64, "Generating");
+
+        let description = self.description(format!("generate {} flashcards", number));
+        let config = &self.config;
+        let count = database::history::record(&mut self.conn, &description, |conn| {
+            crate::create_flashcards_from_expression_list(
+                config,
+                conn,
+                &dict,
+                expression_list,
+                number,
+                &|| progress.inc(),
+            )
+        })?;
+
+        progress.finish("Finished");
+
+        Ok(count)
+    }
+
+    /// Mark the expressions in anki, and mark them as learned from the review intervals of their cards.
+    pub fn sync(&mut self, progress: &dyn Progress) -> Result<SyncSummary, error::Error> {
+        let expression_list = anki::expression_list(&self.config)?;
+
+        let learned_interval = self.config.anki().learned_interval();
+        let interval_map = anki::interval_map(&self.config)?;
+
+        let description = self.description("sync".to_string());
+        let (learned_count, unlearned_count) =
+            database::history::record(&mut self.conn, &description, |conn| {
+                database::reset_in_anki(conn)?;
+
+                for expression in expression_list.iter() {
+                    progress.message(expression);
+                    database::update_in_anki_for_expression(conn, 1, expression)?;
+                }
+
+                // derive is_learned from the review intervals of the cards
+                database::update_is_learned_for_interval_map(conn, &interval_map, learned_interval)
+            })?;
+
+        Ok(SyncSummary {
+            expression_count: expression_list.len(),
+            learned_count,
+            unlearned_count,
+        })
+    }
+
+    /// Run function on the words as expressions and record its changes in the history.
+    fn record_word_list<T>(
+        &mut self,
+        word_list: &[String],
+        description: String,
+        message: (&str, &str),
+        progress: &dyn Progress,
+        function: impl FnOnce(&mut Connection, &[Expression], &dyn Fn()) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, error::Error> {
+        let expression_list: Vec<Expression> = word_list
+            .iter()
+            .map(|x| Expression::new(x.to_string()))
+            .collect();
+
+        let description = self.description(description);
+        progress.start(expression_list.len() as u64, message.0);
+        let result = database::history::record(&mut self.conn, &description, |conn| {
+            function(conn, &expression_list, &|| progress.inc())
+        })?;
+        progress.finish(message.1);
+
+        Ok(result)
+    }
+
+    /// Mark the words as learned, or as not learned.
+    ///
+    /// Returns the number of words that had not been imported and were added.
+    pub fn learn(
+        &mut self,
+        word_list: &[String],
+        is_learned: bool,
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        let (verb, message) = match is_learned {
+            true => ("learn", ("Learning", "Learned")),
+            false => ("unlearn", ("Unlearning", "Unlearned")),
+        };
+        let description = format!("{} {} expressions", verb, word_list.len());
+
+        self.record_word_list(
+            word_list,
+            description,
+            message,
+            progress,
+            |conn, list, callback| {
+                database::update_is_learned_for_expression_list(conn, list, is_learned, callback)
+            },
+        )
+    }
+
+    /// Exclude the words from lists and flashcards, or include them again.
+    pub fn exclude(
+        &mut self,
+        word_list: &[String],
+        is_excluded: bool,
+        progress: &dyn Progress,
+    ) -> Result<(), error::Error> {
+        let (verb, message) = match is_excluded {
+            true => ("exclude", ("Excluding", "Excluded")),
+            false => ("include", ("Including", "Included")),
+        };
+        let description = format!("{} {} expressions", verb, word_list.len());
+
+        self.record_word_list(
+            word_list,
+            description,
+            message,
+            progress,
+            |conn, list, callback| {
+                database::update_is_excluded_for_expression_list(conn, list, is_excluded, callback)
+            },
+        )
+    }
+
+    /// Exclude every expression that occurs with the pos, or include them again.
+    pub fn exclude_pos(
+        &mut self,
+        pos_list: &[String],
+        is_excluded: bool,
+        progress: &dyn Progress,
+    ) -> Result<(), error::Error> {
+        let (verb, message) = match is_excluded {
+            true => ("exclude", ("Excluding", "Excluded")),
+            false => ("include", ("Including", "Included")),
+        };
+        let description = self.description(format!("{} {} pos", verb, pos_list.len()));
+
+        progress.start(pos_list.len() as u64, message.0);
+        database::history::record(&mut self.conn, &description, |conn| {
+            database::update_is_excluded_for_pos_list(conn, pos_list, is_excluded, &|| {
+                progress.inc()
+            })
+        })?;
+        progress.finish(message.1);
+
+        Ok(())
+    }
+
+    /// Match every expression against the exclusion rules in the config, and exclude the matches unless is_dry_run.
+    ///
+    /// Returns the matched expressions with the name of the rule that matched them.
+    pub fn exclude_by_rules(
+        &mut self,
+        is_dry_run: bool,
+    ) -> Result<Vec<(String, String)>, error::Error> {
+        let rule_list = exclusion::compile_rule_list(self.config.exclusion_rules())?;
+        if rule_list.is_empty() {
+            return Err(error::Error::new(
+                error::Kind::Config,
+                "No exclusion rules are configured.",
+            ));
+        }
+
+        let candidate_list = database::select_exclusion_candidate_list(&self.conn, 0)?;
+        let match_list = exclusion::match_list(&rule_list, candidate_list);
+
+        if !is_dry_run && !match_list.is_empty() {
+            let description = self.description(format!(
+                "exclude {} expressions by the exclusion rules",
+                match_list.len()
+            ));
+            crate::exclude_match_list(&mut self.conn, &description, &match_list)?;
+        }
+
+        Ok(match_list
+            .iter()
+            .map(|(candidate, rule)| (candidate.expression.to_string(), rule.name().to_string()))
+            .collect())
+    }
+
+    /// Set the priority of the words, which raises or lowers their score.
+    ///
+    /// Returns the number of words that were skipped because they have not been imported.
+    pub fn prioritize(
+        &mut self,
+        word_list: &[String],
+        priority: i64,
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        let description = format!(
+            "set the priority of {} expressions to {}",
+            word_list.len(),
+            priority
+        );
+
+        self.record_word_list(
+            word_list,
+            description,
+            ("Prioritizing", "Prioritized"),
+            progress,
+            |conn, list, callback| {
+                database::update_priority_for_expression_list(conn, list, priority, callback)
+            },
+        )
+    }
+
+    /// Add the tag to the words, or remove it.
+    ///
+    /// Returns the number of words that were skipped because they have not been imported.
+    pub fn tag(
+        &mut self,
+        tag: &str,
+        word_list: &[String],
+        is_tagged: bool,
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        let (description, message) = match is_tagged {
+            true => (
+                format!("tag {} expressions with {}", word_list.len(), tag),
+                ("Tagging", "Tagged"),
+            ),
+            false => (
+                format!(
+                    "remove the tag {} from {} expressions",
+                    tag,
+                    word_list.len()
+                ),
+                ("Untagging", "Untagged"),
+            ),
+        };
+
+        self.record_word_list(
+            word_list,
+            description,
+            message,
+            progress,
+            |conn, list, callback| {
+                database::update_tag_for_expression_list(conn, tag, list, is_tagged, callback)
+            },
+        )
+    }
+
+    /// The tags with the number of expressions that have them.
+    pub fn tags(&self) -> Result<Vec<(String, i64)>, error::Error> {
+        Ok(database::select_tag_list(&self.conn)?)
+    }
+
+    /// The most recent commands in the history, newest first, a limit of -1 selects every command.
+    pub fn history(&self, limit: i32) -> Result<Vec<HistoryEntry>, error::Error> {
+        Ok(database::history::select_entry_list(&self.conn, limit)?)
+    }
+
+    /// The changes of a command in the history, each with the expression or table row it changed.
+    pub fn changes(&self, id: i64) -> Result<Vec<(String, Change)>, error::Error> {
+        Ok(database::history::select_change_list(&self.conn, id)?)
+    }
+
+    /// Undo the last number commands, stopping at the newest command that cannot be undone.
+    ///
+    /// Returns the commands that were undone and the command that undo stopped at.
+    pub fn undo(&mut self, number: i32) -> Result<UndoSummary, error::Error> {
+        Ok(database::history::undo(&mut self.conn, number)?)
+    }
+
+    /// The pos of the expressions, a limit of -1 selects every pos.
+    pub fn pos_list(
+        &self,
+        is_excluded: bool,
+        is_asc: bool,
+        limit: i32,
+    ) -> Result<Vec<String>, error::Error> {
+        Ok(database::select_pos_list(
+            &self.conn,
+            is_excluded,
+            is_asc,
+            limit,
+        )?)
+    }
+
+    /// Select the expressions like `expressions`, each with its pos, surface strings, tags and up to sentence_limit sentences.
+    pub fn export_records(
+        &self,
+        filter: &Filter,
+        order: Order,
+        is_asc: bool,
+        limit: i32,
+        sentence_limit: i32,
+    ) -> Result<Vec<ExportRecord>, error::Error> {
+        let mut record_list: Vec<ExportRecord> = Vec::new();
+        for (record, _) in self.expressions(filter, order, is_asc, limit)? {
+            let expression = &record.expression;
+
+            let pos = database::select_pos_for_expression(&self.conn, expression)?
+                .into_iter()
+                .unique()
+                .collect();
+            let surface_strings =
+                database::select_surface_string_for_expression(&self.conn, expression)?;
+            let sentences = database::select_example_sentence_for_expression(
+                &self.conn,
+                expression,
+                sentence_limit,
+            )?;
+            let tags = database::select_tag_for_expression(&self.conn, expression)?;
+
+            record_list.push(ExportRecord {
+                expression: record.expression,
+                frequency: record.frequency,
+                rank: record.rank,
+                pos,
+                surface_strings,
+                is_excluded: record.is_excluded,
+                in_anki: record.in_anki,
+                is_learned: record.is_learned,
+                tags,
+                sentences,
+            });
+        }
+
+        Ok(record_list)
+    }
+
+    /// Create a flashcard in anki for each card whose expression has a definition.
+    ///
+    /// Expressions without a definition are excluded. Returns the number of flashcards that were created.
+    pub fn generate_cards(
+        &mut self,
+        card_list: &[Card],
+        progress: &dyn Progress,
+    ) -> Result<usize, error::Error> {
+        let dict = dictionary::connect(&self.config.dictionary_path())?;
+
+        let expression_list: Vec<Expression> = card_list
+            .iter()
+            .map(|card| {
+                let expression = Expression::new(card.expression.to_string());
+
+                match &card.sentence {
+                    Some(sentence) => expression.sentence(vec![sentence.to_string()]),
+                    None => expression,
+                }
+            })
+            .collect();
+        let max = expression_list.len() as i32;
+
+        progress.start(max as u64, "Generating");
+
+        let description = self.description(format!("generate {} flashcards", max));
+        let config = &self.config;
+        let count = database::history::record(&mut self.conn, &description, |conn| {
+            crate::create_flashcards_from_expression_list(
+                config,
+                conn,
+                &dict,
+                expression_list,
+                max,
+                &|| progress.inc(),
+            )
+        })?;
+
+        progress.finish("Finished");
+
+        Ok(count)
+    }
+
+    /// The frequency lists with their number of entries.
+    pub fn frequency_lists(&self) -> Result<Vec<(String, i64)>, error::Error> {
+        Ok(database::select_frequency_list_list(&self.conn)?)
+    }
+
+    /// Import a frequency list from a delimited file or a Yomitan frequency dictionary zip, replacing a list with the same name.
+    ///
+    /// Returns the name of the list and the number of entries that were imported.
+    pub fn import_frequency_list(
+        &mut self,
+        path: &Path,
+        options: &FrequencyListOptions,
+        progress: &dyn Progress,
+    ) -> Result<(String, usize), error::Error> {
+        let message = format!("Failed to read {}", path.display());
+        let file = fs::File::open(path).context(error::Kind::Io, &message)?;
+        let extension = path
+            .extension()
+            .map_or(String::new(), |x| x.to_string_lossy().to_lowercase());
+
+        let (title, entry_list) = match &extension[..] {
+            "zip" => frequency_list::read_yomitan(file).context(error::Kind::Io, &message)?,
+            _ => {
+                let delimiter = match (options.delimiter, &extension[..]) {
+                    (Some(delimiter), _) => delimiter,
+                    (None, "tsv") | (None, "txt") => b'\t',
+                    (None, _) => b',',
+                };
+
+                let entry_list = frequency_list::read_delimited(
+                    file,
+                    &options.column,
+                    options.reading_column.as_ref(),
+                    options.rank_column.as_ref(),
+                    delimiter,
+                    options.has_header,
+                )
+                .context(error::Kind::Io, &message)?;
+
+                (None, entry_list)
+            }
+        };
+
+        // name the list after the dictionary title or the file
+        let name = match (&options.name, title) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(title)) => title,
+            (None, None) => path
+                .file_stem()
+                .map_or(String::new(), |x| x.to_string_lossy().to_string()),
+        };
+
+        progress.start(entry_list.len() as u64, "Importing");
+        let count = database::insert_frequency_list(&mut self.conn, &name, &entry_list, &|| {
+            progress.inc()
+        })?;
+        progress.finish("Imported");
+
+        Ok((name, count))
+    }
+
+    /// Remove a frequency list and the ranks it gave.
+    pub fn remove_frequency_list(&mut self, name: &str) -> Result<(), error::Error> {
+        match database::delete_frequency_list(&mut self.conn, name)? {
+            true => Ok(()),
+            false => Err(error::Error::new(
+                error::Kind::Other,
+                format!("The frequency list {} does not exist.", name),
+            )),
+        }
+    }
+
+    /// Find sentences for the query, each with the strings that matched in it, a limit of -1 selects every sentence.
+    pub fn search(
+        &self,
+        query: &str,
+        search: Search,
+        limit: i32,
+    ) -> Result<Vec<(String, Vec<String>)>, error::Error> {
+        let sentence_list = match search {
+            Search::Lemma => {
+                database::search_sentence_list_for_expression(&self.conn, query, limit)?
+            }
+            Search::Substring => {
+                database::search_sentence_list_for_substring(&self.conn, query, limit)?
+                    .into_iter()
+                    .map(|x| (x, vec![query.to_string()]))
+                    .collect()
+            }
+            Search::Phrase => {
+                // the words of the phrase are adjacent in the sentence
+                let phrase: String = query.split_whitespace().collect();
+
+                database::search_sentence_list(&self.conn, query, limit)?
+                    .into_iter()
+                    .map(|x| (x, vec![phrase.clone()]))
+                    .collect()
+            }
+        };
+
+        Ok(sentence_list)
+    }
+
+    /// Count the frequency of every expression again from its occurrences.
+    ///
+    /// Returns the number of expressions whose frequency was repaired.
+    pub fn recount(&mut self) -> Result<usize, error::Error> {
+        let description = self.description("recount".to_string());
+        database::history::record_irreversible(&self.conn, &description)?;
+
+        Ok(database::recount(&mut self.conn)?)
+    }
+
+    /// The size and sentences of the tokenizer cache.
+    pub fn cache_stats(&self) -> Result<CacheStats, error::Error> {
+        Ok(Cache::connect(&self.config.cache_path())?.stats()?)
+    }
+
+    /// Remove every sentence from the tokenizer cache.
+    ///
+    /// Returns the number of sentences that were removed.
+    pub fn clear_cache(&self) -> Result<usize, error::Error> {
+        Ok(Cache::connect(&self.config.cache_path())?.clear()?)
+    }
+
+    /// The counts and coverage of the corpus.
+    pub fn stats(&self) -> Result<Stats, error::Error> {
+        Ok(stats::select_stats(&self.conn)?)
+    }
+
+    /// The unknown expressions to learn to reach target, a share between 0 and 1, of the tokens of a document.
+    pub fn recommend(&self, document: &str, target: f64) -> Result<Recommendation, error::Error> {
+        let id = database::select_document_id(&self.conn, document)?;
+        let coverage = stats::select_document_coverage(&self.conn, id)?;
+        let expression_list = stats::select_unknown_expression_list_for_document(&self.conn, id)?;
+        let token_count_list: Vec<i64> = expression_list.iter().map(|x| x.1).collect();
+
+        Ok(Recommendation {
+            cards_needed: stats::cards_needed(&coverage, &token_count_list, target),
+            coverage,
+            expression_list,
+        })
+    }
+
+    /// The share of unknown tokens and expressions of every document, easiest first.
+    pub fn difficulties(&self) -> Result<Vec<Difficulty>, error::Error> {
+        Ok(stats::select_document_difficulty_list(&self.conn)?)
+    }
+
+    /// The unknown expressions of the i+1 sentences, each with up to sentence_limit of its sentences.
+    pub fn mine(&self, sentence_limit: i32) -> Result<Vec<MinedExpression>, error::Error> {
+        Ok(database::select_mined_expression_list(
+            &self.conn,
+            sentence_limit,
+        )?)
+    }
+
+    /// The unknown keywords of a document against the rest of the corpus, most distinctive first.
+    pub fn keywords(&self, document: &str, min_count: i64) -> Result<Vec<Keyword>, error::Error> {
+        let id = database::select_document_id(&self.conn, document)?;
+
+        Ok(stats::select_keyword_list(&self.conn, id, min_count)?)
+    }
+
+    /// Split a document into chunks, each with the unknown expressions that first appear in it.
+    ///
+    /// Only expressions that recur at least min_count times after their first occurrence are listed.
+    pub fn plan(
+        &self,
+        document: &str,
+        min_count: i64,
+        split: &Split,
+    ) -> Result<Vec<Chunk>, error::Error> {
+        let id = database::select_document_id(&self.conn, document)?;
+        let sentence_list = stats::select_document_sentence_list(&self.conn, id)?;
+        let occurrence_list = stats::select_first_occurrence_list(&self.conn, id, min_count)?;
+
+        Ok(plan::chunk_list(&sentence_list, occurrence_list, split))
+    }
+
+    /// Merge another vocabulist database into this one, resolving the flags of shared expressions with the policy.
+    pub fn merge(
+        &mut self,
+        path: &Path,
+        policy: &MergePolicy,
+    ) -> Result<MergeSummary, error::Error> {
+        let description = self.description(format!("merge {}", path.display()));
+
+        // the flags of conflicting expressions can be undone
+        Ok(database::history::record(
+            &mut self.conn,
+            &description,
+            |conn| database::merge::merge(conn, path, policy),
+        )?)
+    }
+
+    /// Write a copy of the database to path.
+    pub fn backup(&self, path: &Path, progress: &dyn Progress) -> Result<(), error::Error> {
+        progress.start(0, "Backing up");
+        database::backup(&self.conn, path, &|position, len| {
+            progress.position(position as u64, len as u64)
+        })?;
+        progress.finish("Backed up");
+
+        Ok(())
+    }
+
+    /// Replace the database with a copy written by `backup`.
+    ///
+    /// Returns the path of the copy of the database taken before it was replaced.
+    pub fn restore(
+        &mut self,
+        path: &Path,
+        progress: &dyn Progress,
+    ) -> Result<PathBuf, error::Error> {
+        let database_path = self.config.database_path().to_path_buf();
+
+        progress.start(0, "Restoring");
+        database::restore(&mut self.conn, &database_path, path, &|position, len| {
+            progress.position(position as u64, len as u64)
+        })?;
+        progress.finish("Restored");
+
+        Ok(database::restore_backup_path(&database_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        for name in ["frequency", "score", "recent", "dispersion"].iter() {
+            assert_eq!(Order::parse(name).unwrap().as_str(), *name);
+        }
+
+        assert!(Order::parse("random").is_err());
+    }
+
+    #[test]
+    fn test_history_description() {
+        let directory =
+            std::env::temp_dir().join(format!("vocabulist_description_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let word_list = vec!["猫".to_string(), "犬".to_string()];
+
+        let mut vocabulist = Vocabulist::open(Config::default(directory.clone())).unwrap();
+        vocabulist.learn(&word_list, true, &()).unwrap();
+        vocabulist.tag("動物", &word_list, true, &()).unwrap();
+
+        let mut vocabulist = vocabulist.command_line("unlearn 猫 犬");
+        vocabulist.learn(&word_list, false, &()).unwrap();
+
+        let command_list: Vec<String> = vocabulist
+            .history(-1)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.command)
+            .collect();
+        assert_eq!(
+            command_list,
+            vec![
+                "unlearn 猫 犬",
+                "tag 2 expressions with 動物",
+                "learn 2 expressions"
+            ]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error;
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Read;
+
+/// The column of a delimited file that holds the words.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// a 1-based column index
+    Index(usize),
+    /// the name of a column in the header row
+    Name(String),
+}
+
+impl Column {
+    /// parse a column selector, numbers are treated as 1-based indexes and anything else as a header name
+    pub fn parse(string: &str) -> Result<Column, Box<dyn Error>> {
+        match string.parse::<usize>() {
+            Ok(0) => Err(error::Error::new(error::Kind::Other, "Column indexes start at 1").into()),
+            Ok(index) => Ok(Column::Index(index)),
+            Err(_) => Ok(Column::Name(string.to_string())),
+        }
+    }
+
+    /// the 0-based index of the column in a row
+    pub fn index(&self, header: &csv::StringRecord) -> Result<usize, Box<dyn Error>> {
+        match self {
+            Column::Index(index) => Ok(index - 1),
+            Column::Name(name) => Ok(header
+                .iter()
+                .position(|x| x.trim_start_matches('\u{feff}') == name)
+                .ok_or_else(|| {
+                    error::Error::new(error::Kind::Io, format!("Column {} does not exist", name))
+                })?),
+        }
+    }
+}
+
+/// Parse a delimiter argument, accepting `tab` and `\t` for tab separated files.
+pub fn parse_delimiter(string: &str) -> Result<u8, Box<dyn Error>> {
+    match string {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if string.len() == 1 => Ok(string.as_bytes()[0]),
+        _ => Err(
+            error::Error::new(error::Kind::Other, format!("Invalid delimiter: {}", string)).into(),
+        ),
+    }
+}
+
+/// Remove blank entries and duplicates while keeping the original order.
+fn clean(word_list: Vec<String>) -> Vec<String> {
+    let mut seen: HashSet<String> = HashSet::new();
+
+    word_list
+        .into_iter()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty() && seen.insert(x.clone()))
+        .collect()
+}
+
+/// Read a list of words separated by whitespace.
+pub fn read_plain(reader: impl Read) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut reader = reader;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    // some tools export with a byte order mark
+    let content = content.trim_start_matches('\u{feff}');
+
+    Ok(clean(
+        content.split_whitespace().map(|x| x.to_string()).collect(),
+    ))
+}
+
+/// Read the words in one column of a delimited file, such as a CSV or TSV export from another tool.
+pub fn read_delimited(
+    reader: impl Read,
+    column: &Column,
+    delimiter: u8,
+    has_header: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let has_header = has_header || matches!(column, Column::Name(_));
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(reader);
+
+    let index = column.index(csv_reader.headers()?)?;
+
+    let mut word_list: Vec<String> = Vec::new();
+    for record in csv_reader.records() {
+        if let Some(word) = record?.get(index) {
+            word_list.push(word.to_string());
+        }
+    }
+
+    Ok(clean(word_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_plain() {
+        let content = "\u{feff}猫\n犬  猫\n\n鳥\n";
+
+        let word_list = read_plain(content.as_bytes()).expect("Failed to read word list");
+
+        assert_eq!(word_list, vec!["猫", "犬", "鳥"]);
+    }
+
+    #[test]
+    fn test_read_delimited_index() {
+        let content = "1\t猫\tねこ\n2\t犬\tいぬ\n";
+
+        let word_list = read_delimited(content.as_bytes(), &Column::Index(2), b'\t', false)
+            .expect("Failed to read word list");
+
+        assert_eq!(word_list, vec!["猫", "犬"]);
+    }
+
+    #[test]
+    fn test_read_delimited_name() {
+        let content = "reading,word\nねこ,猫\nいぬ,\"犬\"\n";
+
+        let column = Column::parse("word").unwrap();
+        let word_list = read_delimited(content.as_bytes(), &column, b',', false)
+            .expect("Failed to read word list");
+
+        assert_eq!(word_list, vec!["猫", "犬"]);
+    }
+
+    #[test]
+    fn test_read_delimited_missing_column() {
+        let content = "reading,word\nねこ,猫\n";
+
+        let column = Column::parse("kanji").unwrap();
+
+        assert!(read_delimited(content.as_bytes(), &column, b',', false).is_err());
+    }
+}